@@ -0,0 +1,326 @@
+//! Plays many games with a bot and prints aggregate statistics.
+//!
+//!     cargo run --release --bin simulate -- --strategy expectimax --depth 2 \
+//!         --games 200 [--seed 0] [--threads N] [--size 4] [--format json|csv]
+//!
+//! Game `i` is seeded with `seed + i`, so runs are reproducible whatever
+//! the number of threads.
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use rand_pcg::Pcg32;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::env;
+use std::process;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Instant;
+
+use onehundred_days_of_mogwai::engine::{
+    solve, value_of, Expectimax, Game, Move, Position, Rules, MOVES, WIN_EXP,
+};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Strategy {
+    /// Any legal move.
+    Random,
+    /// The move scoring the most points right now.
+    Greedy,
+    /// Down, then left, then right, up only when nothing else works.
+    Corner,
+    Expectimax(u32),
+}
+
+impl Strategy {
+    fn name(&self) -> String {
+        match self {
+            Strategy::Random => "random".to_string(),
+            Strategy::Greedy => "greedy".to_string(),
+            Strategy::Corner => "corner".to_string(),
+            Strategy::Expectimax(depth) => format!("expectimax-{}", depth),
+        }
+    }
+
+    fn pick(&self, game: &Game, rng: &mut Pcg32) -> Option<Move> {
+        let board = &game.board;
+        match self {
+            Strategy::Random => board.legal_moves().choose(rng).copied(),
+            Strategy::Greedy => MOVES
+                .iter()
+                .filter_map(|mv| Some((*mv, board.clone().slide(*mv)?)))
+                .max_by_key(|(_, score)| *score)
+                .map(|(mv, _)| mv),
+            Strategy::Corner => [Move::Down, Move::Left, Move::Right, Move::Up]
+                .iter()
+                .copied()
+                .find(|mv| board.can_move(*mv)),
+            Strategy::Expectimax(depth) => {
+                let search = Expectimax::new(*depth).with_spawn(game.rules.spawn.clone());
+                solve(&search, board)
+            }
+        }
+    }
+}
+
+impl FromStr for Strategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Strategy, String> {
+        match s {
+            "random" => Ok(Strategy::Random),
+            "greedy" => Ok(Strategy::Greedy),
+            "corner" => Ok(Strategy::Corner),
+            "expectimax" => Ok(Strategy::Expectimax(2)),
+            _ => Err(format!("unknown strategy: {:?}", s)),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Format {
+    Json,
+    Csv,
+}
+
+struct Options {
+    strategy: Strategy,
+    games: u64,
+    seed: u64,
+    threads: usize,
+    rules: Rules,
+    format: Format,
+}
+
+fn parse<T: FromStr>(flag: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid value for {}: {:?}", flag, value))
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut options = Options {
+        strategy: Strategy::Expectimax(2),
+        games: 100,
+        seed: 0,
+        threads: thread::available_parallelism().map_or(1, |n| n.get()),
+        rules: Rules::default(),
+        format: Format::Json,
+    };
+    let mut depth = None;
+
+    let mut args = env::args().skip(1);
+    while let Some(flag) = args.next() {
+        let value = args
+            .next()
+            .ok_or_else(|| format!("{} needs a value", flag))?;
+        match flag.as_str() {
+            "--strategy" => options.strategy = value.parse()?,
+            "--depth" => depth = Some(parse(&flag, &value)?),
+            "--games" => options.games = parse(&flag, &value)?,
+            "--seed" => options.seed = parse(&flag, &value)?,
+            "--threads" => options.threads = parse::<usize>(&flag, &value)?.max(1),
+            "--size" => options.rules = value.parse()?,
+            "--format" => {
+                options.format = match value.as_str() {
+                    "json" => Format::Json,
+                    "csv" => Format::Csv,
+                    _ => return Err(format!("unknown format: {:?}", value)),
+                }
+            }
+            _ => return Err(format!("unknown argument: {}", flag)),
+        }
+    }
+
+    if let (Strategy::Expectimax(_), Some(depth)) = (options.strategy, depth) {
+        options.strategy = Strategy::Expectimax(depth);
+    }
+    Ok(options)
+}
+
+/// How one game ended.
+#[derive(Copy, Clone, Debug)]
+struct GameResult {
+    score: u64,
+    highest: u8,
+    moves: u64,
+}
+
+fn play(strategy: Strategy, seed: u64, rules: &Rules) -> GameResult {
+    let mut game = Game::with_rules(seed, rules.clone());
+    // The bot gets its own stream so its choices don't shift the spawns.
+    let mut rng = Pcg32::seed_from_u64(seed ^ 0x5eed);
+    while let Some(mv) = strategy.pick(&game, &mut rng) {
+        game.play(mv);
+    }
+    GameResult {
+        score: game.score,
+        highest: game.highest,
+        moves: game.moves.len() as u64,
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Report {
+    strategy: String,
+    width: usize,
+    height: usize,
+    games: u64,
+    seed: u64,
+    threads: usize,
+    mean: f64,
+    median: u64,
+    p10: u64,
+    p25: u64,
+    p75: u64,
+    p90: u64,
+    p99: u64,
+    max: u64,
+    win_rate: f64,
+    moves_per_sec: f64,
+    /// Number of games by the value of their highest tile.
+    max_tiles: BTreeMap<u64, u64>,
+}
+
+/// The score below which `p` percent of the sorted `scores` fall.
+fn percentile(scores: &[u64], p: f64) -> u64 {
+    let index = ((scores.len() - 1) as f64 * p / 100.0).round() as usize;
+    scores[index]
+}
+
+fn report(options: &Options, results: &[GameResult], secs: f64) -> Report {
+    let mut scores = results
+        .iter()
+        .map(|result| result.score)
+        .collect::<Vec<_>>();
+    scores.sort_unstable();
+    let games = results.len() as u64;
+    let moves: u64 = results.iter().map(|result| result.moves).sum();
+    let won = results
+        .iter()
+        .filter(|result| result.highest >= WIN_EXP)
+        .count();
+
+    let mut max_tiles = BTreeMap::new();
+    for result in results {
+        *max_tiles.entry(value_of(result.highest)).or_insert(0) += 1;
+    }
+
+    Report {
+        strategy: options.strategy.name(),
+        width: options.rules.width,
+        height: options.rules.height,
+        games,
+        seed: options.seed,
+        threads: options.threads,
+        mean: scores.iter().sum::<u64>() as f64 / games as f64,
+        median: percentile(&scores, 50.0),
+        p10: percentile(&scores, 10.0),
+        p25: percentile(&scores, 25.0),
+        p75: percentile(&scores, 75.0),
+        p90: percentile(&scores, 90.0),
+        p99: percentile(&scores, 99.0),
+        max: *scores.last().unwrap_or(&0),
+        win_rate: won as f64 / games as f64,
+        moves_per_sec: moves as f64 / secs,
+        max_tiles,
+    }
+}
+
+/// One header line and one data line, with a `tile_<value>` column per highest tile seen.
+fn csv(report: &Report) -> String {
+    let mut header = vec![
+        "strategy",
+        "width",
+        "height",
+        "games",
+        "seed",
+        "threads",
+        "mean",
+        "median",
+        "p10",
+        "p25",
+        "p75",
+        "p90",
+        "p99",
+        "max",
+        "win_rate",
+        "moves_per_sec",
+    ]
+    .into_iter()
+    .map(String::from)
+    .collect::<Vec<_>>();
+    let mut row = vec![
+        report.strategy.clone(),
+        report.width.to_string(),
+        report.height.to_string(),
+        report.games.to_string(),
+        report.seed.to_string(),
+        report.threads.to_string(),
+        format!("{:.1}", report.mean),
+        report.median.to_string(),
+        report.p10.to_string(),
+        report.p25.to_string(),
+        report.p75.to_string(),
+        report.p90.to_string(),
+        report.p99.to_string(),
+        report.max.to_string(),
+        format!("{:.4}", report.win_rate),
+        format!("{:.0}", report.moves_per_sec),
+    ];
+    for (tile, count) in report.max_tiles.iter() {
+        header.push(format!("tile_{}", tile));
+        row.push(count.to_string());
+    }
+    format!("{}\n{}\n", header.join(","), row.join(","))
+}
+
+fn run(options: Options) -> Result<String, String> {
+    if options.games == 0 {
+        return Err("--games must be at least 1".to_string());
+    }
+
+    let next = Arc::new(AtomicU64::new(0));
+    let results = Arc::new(Mutex::new(Vec::with_capacity(options.games as usize)));
+    let started = Instant::now();
+
+    let workers = (0..options.threads)
+        .map(|_| {
+            let (next, results) = (next.clone(), results.clone());
+            let (strategy, games, seed) = (options.strategy, options.games, options.seed);
+            let rules = options.rules.clone();
+            thread::spawn(move || loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                if index >= games {
+                    break;
+                }
+                let result = play(strategy, seed.wrapping_add(index), &rules);
+                results.lock().expect("a worker panicked").push(result);
+            })
+        })
+        .collect::<Vec<_>>();
+    for worker in workers {
+        worker
+            .join()
+            .map_err(|_| "a simulation thread panicked".to_string())?;
+    }
+
+    let secs = started.elapsed().as_secs_f64();
+    let results = results.lock().expect("a worker panicked");
+    let report = report(&options, &results, secs);
+    match options.format {
+        Format::Json => serde_json::to_string_pretty(&report).map_err(|err| err.to_string()),
+        Format::Csv => Ok(csv(&report)),
+    }
+}
+
+fn main() {
+    match parse_args().and_then(run) {
+        Ok(output) => println!("{}", output.trim_end()),
+        Err(err) => {
+            eprintln!("{}", err);
+            process::exit(1);
+        }
+    }
+}
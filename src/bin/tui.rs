@@ -0,0 +1,260 @@
+//! Plays 2048 in a terminal, on the same engine as the web front-end.
+//!
+//!     cargo run --bin tui -- [--size 5|4x6] [--seed N] [--save FILE]
+//!
+//! Arrows, WASD or hjkl move, `u` undoes, `r` restarts, Ctrl-S saves and
+//! `q` saves and quits. A saved game is picked up again on the next start,
+//! unless `--size` or `--seed` ask for a new one.
+use std::env;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::process::{self, Command, Stdio};
+
+use onehundred_days_of_mogwai::engine::{value_of, Game, Move, Rules};
+
+const DEFAULT_SAVE: &str = "2048-save.json";
+
+/// Puts the terminal in raw mode for as long as it lives.
+struct RawMode {
+    saved: String,
+}
+
+impl RawMode {
+    fn enable() -> io::Result<RawMode> {
+        let saved = stty(&["-g"])?;
+        stty(&["raw", "-echo"])?;
+        Ok(RawMode {
+            saved: saved.trim().to_string(),
+        })
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        stty(&[&self.saved]).ok();
+    }
+}
+
+fn stty(args: &[&str]) -> io::Result<String> {
+    let output = Command::new("stty")
+        .args(args)
+        .stdin(Stdio::inherit())
+        .output()?;
+    if !output.status.success() {
+        let err = String::from_utf8_lossy(&output.stderr).to_string();
+        return Err(io::Error::other(err));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+enum Key {
+    Move(Move),
+    Undo,
+    Restart,
+    Save,
+    Quit,
+}
+
+/// Reads one key press, skipping bytes that mean nothing to the game.
+fn read_key(stdin: &mut impl Read) -> io::Result<Key> {
+    let mut byte = [0; 1];
+    loop {
+        stdin.read_exact(&mut byte)?;
+        let key = match byte[0] {
+            b'w' | b'k' => Key::Move(Move::Up),
+            b'a' | b'h' => Key::Move(Move::Left),
+            b's' | b'j' => Key::Move(Move::Down),
+            b'd' | b'l' => Key::Move(Move::Right),
+            b'u' => Key::Undo,
+            b'r' => Key::Restart,
+            // Ctrl-S
+            0x13 => Key::Save,
+            // `q` or Ctrl-C
+            b'q' | 0x03 => Key::Quit,
+            // Arrow keys come as `ESC [ A` to `ESC [ D`.
+            0x1b => {
+                let mut seq = [0; 2];
+                stdin.read_exact(&mut seq)?;
+                match seq {
+                    [b'[', b'A'] => Key::Move(Move::Up),
+                    [b'[', b'B'] => Key::Move(Move::Down),
+                    [b'[', b'C'] => Key::Move(Move::Right),
+                    [b'[', b'D'] => Key::Move(Move::Left),
+                    _ => continue,
+                }
+            }
+            _ => continue,
+        };
+        return Ok(key);
+    }
+}
+
+/// 256-colour background and foreground of a tile, by exponent.
+fn colors(exp: u8) -> (u8, u8) {
+    match exp {
+        0 => (239, 250),
+        1 => (255, 237),
+        2 => (230, 237),
+        3 => (215, 255),
+        4 => (209, 255),
+        5 => (203, 255),
+        6 => (196, 255),
+        7 => (229, 237),
+        8 => (228, 237),
+        9 => (227, 237),
+        10 => (220, 237),
+        11 => (214, 237),
+        _ => (235, 255),
+    }
+}
+
+/// Draws the whole screen; lines end in `\r\n` since raw mode doesn't add the `\r`.
+fn render(game: &Game, status: &str) -> String {
+    const CELL: usize = 7;
+    let mut out = String::from("\x1b[2J\x1b[H");
+    out += &format!(
+        "2048  score {}  best tile {}  moves {}\r\n\r\n",
+        game.score,
+        value_of(game.highest),
+        game.moves.len()
+    );
+
+    for row in 0..game.board.height {
+        // Each row is three lines tall, the value on the middle one.
+        for line in 0..3 {
+            for col in 0..game.board.width {
+                let exp = game.board.exp((row, col));
+                let (bg, fg) = colors(exp);
+                let text = if line == 1 && exp != 0 {
+                    value_of(exp).to_string()
+                } else {
+                    String::new()
+                };
+                out += &format!(
+                    "\x1b[48;5;{};38;5;{};1m{:^w$}\x1b[0m ",
+                    bg,
+                    fg,
+                    text,
+                    w = CELL
+                );
+            }
+            out += "\r\n";
+        }
+        out += "\r\n";
+    }
+
+    out += "arrows/wasd/hjkl move  u undo  r restart  ^S save  q quit\r\n";
+    out += status;
+    out += "\r\n";
+    out
+}
+
+struct Options {
+    rules: Option<Rules>,
+    seed: Option<u64>,
+    save: String,
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut options = Options {
+        rules: None,
+        seed: None,
+        save: DEFAULT_SAVE.to_string(),
+    };
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{} needs a value", arg));
+        match arg.as_str() {
+            "--size" => options.rules = Some(value()?.parse()?),
+            "--seed" => {
+                let seed = value()?;
+                options.seed = Some(
+                    seed.parse()
+                        .map_err(|_| format!("invalid seed: {:?}", seed))?,
+                );
+            }
+            "--save" => options.save = value()?,
+            _ => return Err(format!("unknown argument: {}", arg)),
+        }
+    }
+    Ok(options)
+}
+
+fn load(path: &str) -> Option<Game> {
+    let json = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&json).ok()
+}
+
+fn save(game: &Game, path: &str) -> Result<(), String> {
+    let json = serde_json::to_string(game).map_err(|err| err.to_string())?;
+    fs::write(path, json).map_err(|err| format!("couldn't save to {}: {}", path, err))
+}
+
+fn run(options: Options) -> Result<(), String> {
+    let new_game = |rules: &Rules| Game::with_rules(rand::random(), rules.clone());
+    let mut game = match (&options.rules, options.seed) {
+        (None, None) => load(&options.save).unwrap_or_else(Game::random),
+        (rules, seed) => {
+            let rules = rules.clone().unwrap_or_default();
+            Game::with_rules(seed.unwrap_or_else(rand::random), rules)
+        }
+    };
+    // Whole games are small enough to keep one per move for undo.
+    let mut history: Vec<Game> = Vec::new();
+    let mut status = format!("seed {}", game.seed);
+
+    let _raw = RawMode::enable().map_err(|err| format!("couldn't set raw mode: {}", err))?;
+    let stdin = io::stdin();
+    let mut stdin = stdin.lock();
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+
+    loop {
+        stdout
+            .write_all(render(&game, &status).as_bytes())
+            .and_then(|_| stdout.flush())
+            .map_err(|err| err.to_string())?;
+        status.clear();
+
+        match read_key(&mut stdin).map_err(|err| err.to_string())? {
+            Key::Move(mv) => {
+                let before = game.clone();
+                if game.play(mv).changed {
+                    history.push(before);
+                }
+                if game.is_over() {
+                    status = "Game over! u to undo, r to restart".to_string();
+                }
+            }
+            Key::Undo => match history.pop() {
+                Some(previous) => game = previous,
+                None => status = "Nothing to undo".to_string(),
+            },
+            Key::Restart => {
+                history.clear();
+                game = new_game(&game.rules);
+                status = format!("seed {}", game.seed);
+            }
+            Key::Save => {
+                save(&game, &options.save)?;
+                status = format!("Saved to {}", options.save);
+            }
+            Key::Quit => {
+                if game.is_over() {
+                    fs::remove_file(&options.save).ok();
+                } else {
+                    save(&game, &options.save)?;
+                }
+                return Ok(());
+            }
+        }
+    }
+}
+
+fn main() {
+    let result = parse_args().and_then(run);
+    if let Err(err) = result {
+        eprintln!("{}", err);
+        process::exit(1);
+    }
+}
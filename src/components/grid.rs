@@ -1,221 +1,154 @@
-use log::info;
 use mogwai::prelude::*;
-use rand::distributions::{Distribution, Standard};
-use rand::Rng;
-use std::collections::hash_map::DefaultHasher;
-use std::hash::{Hash, Hasher};
 
-#[derive(Debug, Copy, Clone, PartialEq, Hash)]
-pub struct Grid {
-    pub data: [[u16; 4]; 4],
-    pub score: u16,
-    pub score_add: u16,
-    pub highest: u16,
-}
-
-impl Grid {
-    pub fn add_random_2(&mut self) {
-        let mut rng = rand::thread_rng();
-        let mut rand_grid_point: GridPoint = rng.gen();
-
-        while self.data[rand_grid_point.0 as usize][rand_grid_point.1 as usize] != 0 {
-            rand_grid_point = rng.gen();
-        }
-
-        self.data[rand_grid_point.0 as usize][rand_grid_point.1 as usize] = 2;
-    }
-
-    pub fn move_up(&mut self) -> (u16, u16, u16) {
-        self.transpose();
-        self.move_left();
-        self.transpose();
-        (self.score, self.score_add, self.highest)
-    }
-
-    pub fn move_left(&mut self) -> (u16, u16, u16) {
-        self.compress();
-        self.merge();
-        self.compress();
-        (self.score, self.score_add, self.highest)
-    }
-
-    pub fn move_right(&mut self) -> (u16, u16, u16) {
-        self.reverse();
-        self.move_left();
-        self.reverse();
-        (self.score, self.score_add, self.highest)
-    }
-    pub fn move_down(&mut self) -> (u16, u16, u16) {
-        self.transpose();
-        self.move_right();
-        self.transpose();
-        (self.score, self.score_add, self.highest)
-    }
-
-    pub fn transpose(&mut self) {
-        let mut out = [[0; 4]; 4];
-        for col in 0..4 {
-            for row in 0..4 {
-                out[col][row] = self.data[row][col];
-            }
-        }
-        self.data = out;
-    }
-
-    pub fn compress(&mut self) {
-        let mut changed = false;
-        let mut new_grid = [[0; 4]; 4];
-
-        for i in 0..4 {
-            let mut pos = 0;
-            for j in 0..4 {
-                if self.data[i][j] != 0 {
-                    new_grid[i][pos] = self.data[i][j];
-                    if j != pos {
-                        changed = true;
-                    }
-                    pos += 1;
-                }
-            }
-        }
-        self.data = new_grid;
-    }
-
-    pub fn merge(&mut self) {
-        let mut changed = false;
-
-        for i in 0..4 {
-            for j in 0..3 {
-                if self.data[i][j] == self.data[i][j + 1] && self.data[i][j] != 0 {
-                    let add = self.data[i][j] * 2;
-                    self.score_add = self.data[i][j];
-                    self.data[i][j] = add;
-                    self.score += add;
-                    self.data[i][j + 1] = 0;
-
-                    if self.data[i][j] > self.highest {
-                        self.highest = self.data[i][j];
-                    }
-                    changed = true;
-                }
-            }
-        }
-    }
+use crate::engine::{value_of, Board, Cell, MoveOutcome, Tile};
 
-    pub fn reverse(&mut self) {
-        let mut new_grid = [[0; 4]; 4];
+const BOARD_SIZE: f64 = 320.0;
+const TILE_GAP: f64 = 10.0;
 
-        for i in 0..4 {
-            for j in 0..4 {
-                new_grid[i][j] = self.data[i][3 - j];
-            }
-        }
+/// New position and exponent of a card that took part in a move.
+#[derive(Clone, Debug)]
+pub struct CardUpdate {
+    pub id: u32,
+    pub cell: Cell,
+    pub exp: u8,
+    pub merged: bool,
+}
 
-        self.data = new_grid;
+impl CardUpdate {
+    fn class(&self) -> String {
+        card_class(self.exp, self.cell, if self.merged { "merged" } else { "" })
     }
-    pub fn base_grid_view(&self) -> ViewBuilder<HtmlElement> {
-        let grid = self.data;
-        builder! {
-            <div class="tiles">
-                { if grid[0][0] != 0 { render_card(grid[0][0], 0, 0)  } else{builder!{<span></span>}}}
-               { if grid[0][1] != 0 { render_card(grid[0][1], 0, 1)  } else{builder!{<span></span>}}}
-               { if grid[0][2] != 0 { render_card(grid[0][2], 0, 2)  } else{builder!{<span></span>}}}
-               { if grid[0][3] != 0 { render_card(grid[0][3], 0, 3)  } else{builder!{<span></span>}}}
-
-               { if grid[1][0] != 0 { render_card(grid[1][0], 1, 0)  } else{builder!{<span></span>}}}
-               { if grid[1][1] != 0 { render_card(grid[1][1], 1, 1)  } else{builder!{<span></span>}}}
-               { if grid[1][2] != 0 { render_card(grid[1][2], 1, 2)  } else{builder!{<span></span>}}}
-               { if grid[1][3] != 0 { render_card(grid[1][3], 1, 3)  } else{builder!{<span></span>}}}
-
-               { if grid[2][0] != 0 { render_card(grid[2][0], 2, 0)  } else{builder!{<span></span>}}}
-               { if grid[2][1] != 0 { render_card(grid[2][1], 2, 1)  } else{builder!{<span></span>}}}
-               { if grid[2][2] != 0 { render_card(grid[2][2], 2, 2)  } else{builder!{<span></span>}}}
-               { if grid[2][3] != 0 { render_card(grid[2][3], 2, 3)  } else{builder!{<span></span>}}}
-
-               { if grid[3][0] != 0 { render_card(grid[3][0], 3, 0)  } else{builder!{<span></span>}}}
-               { if grid[3][1] != 0 { render_card(grid[3][1], 3, 1)  } else{builder!{<span></span>}}}
-               { if grid[3][2] != 0 { render_card(grid[3][2], 3, 2)  } else{builder!{<span></span>}}}
-               { if grid[3][3] != 0 { render_card(grid[3][3], 3, 3)  } else{builder!{<span></span>}}}
-
-           </div>
+}
 
-        }
-    }
-    pub fn is_same(&self, other: Grid) -> bool {
-        let mut is_same = true;
-        for (ind, item) in self.data.iter().enumerate() {
-            for (pos, elem) in item.iter().enumerate() {
-                if other.data[ind][pos] != *elem {
-                    is_same = false;
-                    break;
-                }
-            }
-        }
+/// Changes to the list of cards, addressed by their index inside `.tiles`.
+#[derive(Clone, Debug)]
+pub enum TilesPatch {
+    Add(Tile, Cell),
+    Remove(usize),
+}
 
-        is_same
-    }
-    pub fn is_full(&self) -> bool {
-        let mut is_full = true;
-        for item in self.data.iter() {
-            for elem in item.iter() {
-                if *elem == 0 {
-                    is_full = false;
-                    break;
-                }
-            }
-        }
-        info!("is full?: {:?}", self.data);
-        is_full
-    }
+pub fn card_updates(outcome: &MoveOutcome) -> Vec<CardUpdate> {
+    let slides = outcome.slides.iter().map(|slide| CardUpdate {
+        id: slide.id,
+        cell: slide.to,
+        exp: slide.exp,
+        merged: false,
+    });
+    let merges = outcome.merges.iter().flat_map(|merge| {
+        vec![
+            CardUpdate {
+                id: merge.ids[0],
+                cell: merge.to,
+                exp: merge.exp,
+                merged: true,
+            },
+            // The consumed card slides under the merged one until it is removed.
+            CardUpdate {
+                id: merge.ids[1],
+                cell: merge.to,
+                exp: merge.exp - 1,
+                merged: false,
+            },
+        ]
+    });
+    slides.chain(merges).collect()
 }
 
-pub fn render_card(value: u16, col: u16, row: u16) -> ViewBuilder<HtmlElement> {
-    builder! {
+pub fn base_grid_view(
+    board: &Board,
+    updates: &Receiver<Vec<CardUpdate>>,
+    patches: &Receiver<TilesPatch>,
+) -> ViewBuilder<HtmlElement> {
+    let card_updates = updates.branch();
+    let mut tiles = builder! {
         <div
-            class={
-                format!(
-                    "card card__{val} position__{c}_{r} {small}",
-                    val = value.to_string(),
-                    c = col,
-                    r = row,
-                    small = if value > 64 { "small" } else { "" }
-                )
-            }
+            class="tiles"
+            patch:children=patches.branch_filter_map(move |patch| match patch {
+                TilesPatch::Add(tile, cell) => Some(Patch::PushBack {
+                    value: render_card(*tile, *cell, "new", &card_updates),
+                }),
+                TilesPatch::Remove(index) => Some(Patch::Remove { index: *index }),
+            })
         >
-            {value.to_string()}
         </div>
+    };
+    for (cell, tile) in board.tiles() {
+        tiles.with(render_card(tile, cell, "", updates));
     }
+    tiles
 }
 
-pub fn render_board() -> ViewBuilder<HtmlElement> {
+pub fn card_class(exp: u8, cell: Cell, state: &str) -> String {
+    let value = value_of(exp);
+    format!(
+        "card card__{val} position__{r}_{c} {small} {state}",
+        val = value.to_string(),
+        r = cell.0,
+        c = cell.1,
+        small = if value > 8192 {
+            "tiny"
+        } else if value > 64 {
+            "small"
+        } else {
+            ""
+        },
+        state = state
+    )
+}
+
+pub fn render_card(
+    tile: Tile,
+    cell: Cell,
+    state: &str,
+    updates: &Receiver<Vec<CardUpdate>>,
+) -> ViewBuilder<HtmlElement> {
+    let id = tile.id;
     builder! {
-        <div class="board">
-               <div class="tile"></div>
-               <div class="tile"></div>
-               <div class="tile"></div>
-               <div class="tile"></div>
-               <div class="tile"></div>
-               <div class="tile"></div>
-               <div class="tile"></div>
-               <div class="tile"></div>
-               <div class="tile"></div>
-               <div class="tile"></div>
-               <div class="tile"></div>
-               <div class="tile"></div>
-               <div class="tile"></div>
-               <div class="tile"></div>
-               <div class="tile"></div>
-               <div class="tile"></div>
+        <div
+            class=(
+                card_class(tile.exp, cell, state),
+                updates.branch_filter_map(move |ups: &Vec<CardUpdate>| {
+                    ups.iter().find(|up| up.id == id).map(CardUpdate::class)
+                })
+            )
+        >
+            {(
+                tile.value().to_string(),
+                updates.branch_filter_map(move |ups: &Vec<CardUpdate>| {
+                    ups.iter().find(|up| up.id == id).map(|up| value_of(up.exp).to_string())
+                })
+            )}
         </div>
     }
 }
 
-#[derive(Debug)]
-struct GridPoint(u16, u16);
+/// Sizes the wrapper so that the longest side of any board fits in the same
+/// space; cards and cells read their size from `--tile-size`.
+pub fn board_style(board: &Board) -> String {
+    let (width, height) = (board.width as f64, board.height as f64);
+    let tile_size = (BOARD_SIZE - TILE_GAP * (width.max(height) + 1.0)) / width.max(height);
+    format!(
+        "--tile-size: {tile}px; --tile-gap: {gap}px; width: {w}px; height: {h}px;",
+        tile = tile_size,
+        gap = TILE_GAP,
+        w = tile_size * width + TILE_GAP * (width + 1.0),
+        h = tile_size * height + TILE_GAP * (height + 1.0),
+    )
+}
 
-impl Distribution<GridPoint> for Standard {
-    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> GridPoint {
-        let (rand_x, rand_y) = (rng.gen_range(0..4), rng.gen_range(0..4));
-        GridPoint(rand_x, rand_y)
+pub fn render_board(width: usize, height: usize) -> ViewBuilder<HtmlElement> {
+    let mut board = builder! {
+        <div
+            class="board"
+            style=format!(
+                "grid-template-columns: repeat({}, 1fr); grid-template-rows: repeat({}, 1fr);",
+                width, height
+            )
+        >
+        </div>
+    };
+    for _ in 0..width * height {
+        board.with(builder! { <div class="tile"></div> });
     }
+    board
 }
@@ -0,0 +1,76 @@
+use mogwai::prelude::*;
+
+use crate::daily::{self, Daily, DayResult};
+use crate::engine::value_of;
+use crate::records;
+use crate::router::Route;
+
+#[derive(Clone)]
+pub enum In {}
+
+#[derive(Clone)]
+pub enum Out {}
+
+/// The results of a daily challenge already played.
+pub struct DailyPage {
+    pub day: i64,
+    pub result: DayResult,
+    pub daily: Daily,
+}
+
+impl Component for DailyPage {
+    type ModelMsg = In;
+    type ViewMsg = Out;
+    type DomNode = HtmlElement;
+
+    fn update(&mut self, _msg: &In, _tx_view: &Transmitter<Out>, _sub: &Subscriber<In>) {}
+
+    fn view(&self, _tx: &Transmitter<In>, _rx: &Receiver<Out>) -> ViewBuilder<HtmlElement> {
+        let result = &self.result;
+
+        // The game may have been dropped from the recorded games since.
+        let mut actions = builder! {
+            <div class="daily__actions"></div>
+        };
+        if let Some(record) = records::load(&result.replay) {
+            let replay = Route::Replay {
+                id: Some(result.replay.clone()),
+            };
+            actions.with(builder! {
+                <a class="button green play" title="watch your game again" href=String::from(replay)>
+                    "Replay"
+                </a>
+            });
+            actions.with(builder! {
+                <a class="button green play" title="link to your game" href=String::from(Route::SharedGame { record })>
+                    "Share"
+                </a>
+            });
+        }
+
+        builder! {
+            <div class="daily">
+                <a class="button green back_home" href="#/">
+                    <span class="circle">"←"</span>
+                </a>
+                <h1>{format!("Daily {}", daily::date(self.day))}</h1>
+                <h2>{if result.won { "You reached 2048!" } else { "Come back tomorrow for a new game" }}</h2>
+                <dl class="daily__summary">
+                    <dt>"Score"</dt>
+                    <dd>{result.score.to_string()}</dd>
+                    <dt>"Best tile"</dt>
+                    <dd>{value_of(result.highest).to_string()}</dd>
+                    <dt>"Moves"</dt>
+                    <dd>{result.moves.to_string()}</dd>
+                    <dt>"Streak"</dt>
+                    <dd>{format!("{} days", self.daily.streak(self.day))}</dd>
+                    <dt>"Best streak"</dt>
+                    <dd>{format!("{} days", self.daily.best_streak())}</dd>
+                    <dt>"Days played"</dt>
+                    <dd>{self.daily.days.len().to_string()}</dd>
+                </dl>
+                {actions}
+            </div>
+        }
+    }
+}
@@ -3,10 +3,13 @@ use wasm_bindgen::JsCast;
 use mogwai::prelude::*;
 
 use crate::components::metaball::*;
+use crate::containers::play::Play;
+use crate::router::Route;
 
 #[derive(Clone)]
 pub enum In {
     CanvasIn(web_sys::HtmlElement),
+    NewGame,
 }
 
 #[derive(Clone)]
@@ -63,12 +66,31 @@ impl Component for Home {
                     true
                 });
             }
+            In::NewGame => {
+                Play::discard_saved();
+                window()
+                    .location()
+                    .set_hash(&String::from(Route::play()))
+                    .expect("Couldn't go to #/play");
+            }
         }
     }
 
     fn view(&self, tx: &Transmitter<In>, _rx: &Receiver<Out>) -> ViewBuilder<HtmlElement> {
         let w = body().client_width() as usize;
         let h = window().inner_height().unwrap();
+        let actions = match Play::resume().filter(|play| !play.game_over) {
+            Some(play) => builder! {
+                <div class="home__resume">
+                    <p>{format!("Game in progress: {} points", play.game.score)}</p>
+                    <a class="button green" href=String::from(Route::play())>"Resume"</a>
+                    <a class="button green" on:click=tx.contra_map(|_| In::NewGame)>"New game"</a>
+                </div>
+            },
+            None => builder! {
+                <a class="button green start" href=String::from(Route::play())>"Start game"</a>
+            },
+        };
         builder! {
             <div class="home">
                 <canvas
@@ -83,7 +105,7 @@ impl Component for Home {
                     <h1 class="buble__title">"2048"</h1>
                     <h2 class="bubble__subtitle">"mogwai"</h2>
                 </div>
-                <a class="button green start" href="#/play">"Start game"</a>
+                {actions}
             </div>
         }
     }
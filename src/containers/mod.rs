@@ -1,2 +1,7 @@
+pub mod daily;
 pub mod home;
 pub mod play;
+pub mod replay;
+pub mod settings;
+pub mod stats;
+pub mod win;
@@ -1,70 +1,642 @@
 use log::info;
 use mogwai::prelude::*;
+use rand_pcg::Pcg32;
+use serde::{Deserialize, Serialize};
 use wasm_bindgen::prelude::*;
-use web_sys::KeyboardEvent;
+use web_sys::{Gamepad, GamepadButton, HtmlInputElement, KeyboardEvent, PointerEvent};
 
 use crate::components::grid::*;
-use crate::router::Route;
+use crate::daily::{self, Daily};
+use crate::engine::code::{board_code, game_code};
+use crate::engine::{
+    solve, value_of, Board, Budgeted, Expectimax, Game, GameRecord, Move, MoveOutcome, Tile,
+    WIN_EXP,
+};
+use crate::input::{Command, KeyBindings, PadInput, PadState, Point, Swipe};
+use crate::records;
+use crate::router::{HashRoute, Route};
+use crate::stats::Stats;
+use crate::storage;
 
 #[cfg(feature = "wee_alloc")]
 #[global_allocator]
 static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 
-#[derive(Copy, Clone, Debug)]
-pub enum Move {
-    Up,
-    Left,
-    Down,
-    Right,
+/// Milliseconds a hint may search for before answering.
+const HINT_BUDGET: f64 = 150.0;
+
+/// Milliseconds auto-play may search for each move; without a limit, large
+/// boards that don't fit a `BitBoard` would freeze the page.
+const AUTO_BUDGET: f64 = 100.0;
+
+/// localStorage key of the game in progress.
+const SAVED_GAME: &str = "2048.game";
+
+/// localStorage key of the daily challenge in progress.
+const SAVED_DAILY: &str = "2048.daily.game";
+
+/// Everything needed to put the game back exactly as it was before a move.
+#[derive(Clone, Debug)]
+pub struct Snapshot {
+    pub board: Board,
+    pub rng: Pcg32,
+    pub score: u64,
+    pub highest: u8,
+    /// Number of moves played so far.
+    pub moves: usize,
+    pub win: bool,
+    pub game_over: bool,
+}
+
+/// The AI playing on its own, one move every `delay` milliseconds.
+#[derive(Clone, Debug)]
+pub struct AutoPlay {
+    pub on: bool,
+    /// Bumped on every start, so steps scheduled by an earlier run are dropped.
+    pub run: u32,
+    pub delay: i32,
+    pub solver: Expectimax,
+}
+
+impl Default for AutoPlay {
+    fn default() -> Self {
+        AutoPlay {
+            on: false,
+            run: 0,
+            delay: 200,
+            solver: Expectimax::default(),
+        }
+    }
 }
 
-#[derive(Debug)]
+/// A new highest tile reached at or after 2048.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Milestone {
+    pub exp: u8,
+    /// Number of moves played when the tile appeared.
+    pub moves: usize,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Play {
-    pub grid: Vec<Grid>,
+    pub game: Game,
+    /// Rebuilt from `game` when a saved game is loaded, rather than stored.
+    #[serde(skip)]
+    pub history: Vec<Snapshot>,
+    /// Moves taken back by undo; replaying one reproduces the same spawn.
+    pub redo: Vec<Move>,
+    /// How many undos a game allows, `None` for unlimited.
+    pub undo_budget: Option<u32>,
+    pub undos_used: u32,
+    #[serde(default)]
+    pub hints_used: u32,
     pub last_move: Option<Move>,
     pub game_over: bool,
     pub win: bool,
-    pub score: u32,
-    pub score_add: u16,
-    pub highest: u16,
+    /// Set once the player chose to go on after reaching 2048.
+    pub keep_playing: bool,
+    pub milestones: Vec<Milestone>,
+    /// Set once the game has been counted in the lifetime statistics.
+    pub recorded: bool,
+    /// The day of the daily challenge this game is, `None` for free play.
+    #[serde(default)]
+    pub daily: Option<i64>,
+    /// Best score ever, this game included.
+    #[serde(skip, default = "best_score")]
+    pub best: u64,
+    pub score_add: u64,
+    #[serde(skip)]
+    pub auto: AutoPlay,
+    #[serde(skip, default = "KeyBindings::load")]
+    pub bindings: KeyBindings,
+    /// Where the pointer currently pressed on the board went down.
+    #[serde(skip)]
+    pub touch_start: Option<Point>,
+    /// `performance.now()` when the game started, in milliseconds.
+    #[serde(skip, default = "now")]
+    pub started: f64,
+    /// Milliseconds played when the game was last saved.
+    pub elapsed: f64,
+    /// When each move of `game.moves` was played, in milliseconds since the start.
+    #[serde(default)]
+    pub move_times: Vec<f64>,
+    /// Ids of the cards inside `.tiles`, in DOM order.
+    #[serde(skip)]
+    pub cards: Vec<u32>,
+    /// Cards merged away by the last move, removed once their slide is over.
+    #[serde(skip)]
+    pub consumed: Vec<u32>,
 }
 
 #[derive(Clone, Debug)]
 pub enum PlayModelIn {
-    KeyUp(Option<Event>),
-    Restart,
-    Undo,
+    KeyDown(Option<Event>),
+    /// The view is in the page; start polling gamepads until it leaves.
+    Mounted(HtmlElement),
+    PointerDown(Option<Event>),
+    PointerUp(Option<Event>),
+    PointerCancel,
+    Command(Command),
+    ToggleAuto,
+    AutoStep(u32),
+    AutoDelay(Option<Event>),
+    KeepPlaying,
+    ShowResults,
+    Share,
 }
 
 #[derive(Clone)]
 pub enum PlayViewOut {
-    Moved(Option<Move>, Grid, u32, u16, u16),
+    Moved(Option<Move>, MoveOutcome, u64, u64, u8),
+    Reset(Board, u64, u8),
+    Tiles(TilesPatch),
     Win,
+    Milestone(u64),
+    Best(u64),
+    Hint(Option<Move>),
+    Auto(bool),
+    KeepPlaying,
     GameOver,
+    /// A link to the position, or to the whole game once it's over.
+    Share(String),
 }
 
 impl Default for Play {
     fn default() -> Self {
-        let mut grid: Grid = Grid {
-            data: [[0; 4]; 4],
-            score: 0,
-            score_add: 0,
-            highest: 0,
-        };
-        grid.add_random_2();
+        Play::new(Game::random())
+    }
+}
+
+impl Play {
+    /// Wraps an already started game, e.g. one built with a custom `SpawnPolicy`.
+    pub fn new(game: Game) -> Self {
+        info!("seed: {}", game.seed);
 
         Play {
-            grid: vec![grid],
+            cards: card_ids(&game.board),
+            consumed: Vec::new(),
+            game,
+            history: Vec::new(),
+            redo: Vec::new(),
+            undo_budget: None,
+            undos_used: 0,
+            hints_used: 0,
             last_move: None,
             game_over: false,
             win: false,
-            score: 0,
+            keep_playing: false,
+            milestones: Vec::new(),
+            recorded: false,
+            daily: None,
+            best: best_score(),
             score_add: 0,
-            highest: 0,
+            auto: AutoPlay::default(),
+            bindings: KeyBindings::load(),
+            touch_start: None,
+            started: now(),
+            elapsed: 0.0,
+            move_times: Vec::new(),
+        }
+    }
+
+    /// `day`'s challenge: the shared seed, no undo and no help from the solver.
+    /// Reaching 2048 doesn't stop it, only the final score counts.
+    pub fn daily(day: i64) -> Self {
+        let mut play = Play::new(Game::new(daily::seed(day))).with_undo_budget(0);
+        play.daily = Some(day);
+        play.keep_playing = true;
+        play
+    }
+
+    /// The game saved in localStorage by the last session, if any.
+    pub fn resume() -> Option<Play> {
+        Play::load(SAVED_GAME)
+    }
+
+    /// `day`'s challenge if it was left unfinished. One left from an earlier
+    /// day counts as that day's attempt and is dropped.
+    pub fn resume_daily(day: i64) -> Option<Play> {
+        let mut play = Play::load(SAVED_DAILY)?;
+        if play.daily != Some(day) {
+            play.record();
+            storage::remove(SAVED_DAILY);
+            return None;
+        }
+        Some(play)
+    }
+
+    fn load(key: &str) -> Option<Play> {
+        let mut play: Play = storage::load(key)?;
+        info!("resuming seed: {}", play.game.seed);
+        play.history = rebuild_history(&play.game);
+        play.started = now() - play.elapsed;
+        play.cards = card_ids(&play.game.board);
+        Some(play)
+    }
+
+    /// Drops the saved game, counting it in the statistics if it wasn't already.
+    pub fn discard_saved() {
+        if let Some(mut play) = storage::load::<Play>(SAVED_GAME) {
+            play.record();
+        }
+        storage::remove(SAVED_GAME);
+    }
+
+    /// Adds the game to the lifetime statistics and keeps it for replay,
+    /// once, if anything was played.
+    fn record(&mut self) {
+        if !self.recorded && !self.game.moves.is_empty() {
+            self.recorded = true;
+            Stats::record(&self.game, self.win, self.hints_used);
+            let variant = if self.daily.is_some() { "daily" } else { "classic" };
+            let id = records::save(&GameRecord::new(&self.game, variant, &self.move_times));
+            if let Some(day) = self.daily {
+                Daily::record(day, &self.game, self.win, id);
+            }
+        }
+    }
+
+    fn save(&mut self) {
+        self.elapsed = now() - self.started;
+        let key = if self.daily.is_some() {
+            SAVED_DAILY
+        } else {
+            SAVED_GAME
+        };
+        storage::save(key, self);
+    }
+
+    /// Moves are ignored once the game is over or while the win overlay waits for an answer.
+    pub fn is_paused(&self) -> bool {
+        self.game_over || (self.win && !self.keep_playing)
+    }
+
+    fn win_route(&self) -> Route {
+        Route::Win {
+            score: self.game.score,
+            tile: value_of(self.game.highest),
+            moves: self.game.moves.len(),
+            secs: ((now() - self.started) / 1000.0) as u64,
+        }
+    }
+
+    /// The position as a `#/play?board=` link while playing, the whole game
+    /// as a `#/replay?g=` link once it's over.
+    fn share_link(&self) -> Result<String, String> {
+        let hash = if self.game_over {
+            HashRoute::new(&["replay"]).with_param("g", game_code(&self.game)?)
+        } else {
+            HashRoute::new(&["play"]).with_param("board", board_code(&self.game.board)?)
+        };
+        let page = window().location().href().map_err(|err| format!("{:?}", err))?;
+        let page = page.split('#').next().unwrap_or_default();
+        Ok(format!("{}{}", page, hash))
+    }
+
+    /// Limits the number of undos per game, for challenge play.
+    pub fn with_undo_budget(mut self, budget: u32) -> Self {
+        self.undo_budget = Some(budget);
+        self
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.history.is_empty()
+            && self
+                .undo_budget
+                .map_or(true, |budget| self.undos_used < budget)
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            board: self.game.board.clone(),
+            rng: self.game.rng.clone(),
+            score: self.game.score,
+            highest: self.game.highest,
+            moves: self.game.moves.len(),
+            win: self.win,
+            game_over: self.game_over,
+        }
+    }
+
+    fn restore(&mut self, snapshot: Snapshot) {
+        let moves = snapshot.moves;
+        self.game.board = snapshot.board;
+        self.game.rng = snapshot.rng;
+        self.game.score = snapshot.score;
+        self.game.highest = snapshot.highest;
+        self.game.moves.truncate(moves);
+        self.move_times.truncate(moves);
+        self.win = snapshot.win;
+        self.game_over = snapshot.game_over;
+        self.milestones.retain(|milestone| milestone.moves <= moves);
+    }
+
+    fn command(&mut self, command: Command, tx: &Transmitter<PlayViewOut>) {
+        match command {
+            Command::Move(mv) => self.input_move(mv, tx),
+            // A daily game gets one attempt; once it's over its results take its place.
+            Command::Restart if self.daily.is_some() => {
+                if self.game_over {
+                    window()
+                        .location()
+                        .set_hash(&String::from(Route::Daily))
+                        .expect("Couldn't open the daily results");
+                }
+            }
+            Command::Restart => {
+                self.record();
+                let new = Play::new(Game::with_rules(rand::random(), self.game.rules.clone()));
+                self.game = new.game;
+                self.history = new.history;
+                self.redo = new.redo;
+                self.undos_used = new.undos_used;
+                self.hints_used = new.hints_used;
+                self.last_move = new.last_move;
+                self.game_over = new.game_over;
+                self.win = new.win;
+                self.keep_playing = new.keep_playing;
+                self.milestones = new.milestones;
+                self.recorded = new.recorded;
+                self.started = new.started;
+                self.elapsed = new.elapsed;
+                self.move_times = new.move_times;
+                self.send_reset(tx);
+            }
+            Command::Undo => {
+                if !self.can_undo() {
+                    info!("Nothing to undo or no undos left");
+                    return;
+                }
+                if let Some(snapshot) = self.history.pop() {
+                    if let Some(mv) = self.game.moves.last() {
+                        self.redo.push(*mv);
+                    }
+                    self.restore(snapshot);
+                    self.undos_used += 1;
+                    self.last_move = None;
+                    self.send_reset(tx);
+                }
+            }
+            Command::Redo => {
+                if self.is_paused() {
+                    return;
+                }
+                if let Some(mv) = self.redo.pop() {
+                    self.play_move(mv, tx);
+                }
+            }
+            // Daily results are compared between players, so the solver sits them out.
+            Command::Hint if self.daily.is_some() => {}
+            Command::Hint => {
+                if self.is_paused() {
+                    return;
+                }
+                let solver = Expectimax {
+                    depth: 5,
+                    spawn: self.game.rules.spawn.clone(),
+                    ..Expectimax::default()
+                };
+                let started = now();
+                let hint = search(&solver, &self.game.board, HINT_BUDGET);
+                info!("hint: {:?} in {:.0}ms", hint, now() - started);
+                if hint.is_some() {
+                    self.hints_used += 1;
+                }
+                tx.send(&PlayViewOut::Hint(hint));
+            }
+        }
+    }
+
+    /// Lets the solver pick and play the next move, then schedules the one after.
+    fn auto_step(&mut self, tx: &Transmitter<PlayViewOut>, sub: &Subscriber<PlayModelIn>) {
+        let rules = &self.game.rules;
+        let solver = Expectimax {
+            spawn: rules.spawn.clone(),
+            ..self.auto.solver.clone()
+        };
+        let mv = if self.is_paused() {
+            None
+        } else {
+            search(&solver, &self.game.board, AUTO_BUDGET)
+        };
+
+        match mv {
+            Some(mv) => {
+                self.input_move(mv, tx);
+                send_later(sub, self.auto.delay, PlayModelIn::AutoStep(self.auto.run));
+            }
+            None => {
+                self.auto.on = false;
+                tx.send(&PlayViewOut::Auto(false));
+            }
+        }
+    }
+
+    /// Plays a move the player asked for, which makes the redo stack obsolete.
+    fn input_move(&mut self, mv: Move, tx: &Transmitter<PlayViewOut>) {
+        if !self.is_paused() && self.play_move(mv, tx) {
+            self.redo.clear();
+        }
+    }
+
+    /// Plays `mv` and tells the view about it; returns whether the board changed.
+    fn play_move(&mut self, mv: Move, tx: &Transmitter<PlayViewOut>) -> bool {
+        let snapshot = self.snapshot();
+        let previous = self.game.highest;
+        let outcome = self.game.play(mv);
+        if !outcome.changed {
+            return false;
+        }
+
+        self.history.push(snapshot);
+        self.move_times.push(now() - self.started);
+        self.last_move = Some(mv);
+        self.score_add = outcome.score;
+        info!("mv_score:{}, res_score:{}", outcome.score, self.game.score);
+        self.send_moved(outcome, tx);
+
+        if self.game.highest > previous && self.game.highest >= WIN_EXP {
+            self.milestones.push(Milestone {
+                exp: self.game.highest,
+                moves: self.game.moves.len(),
+            });
+            if !self.win {
+                info!("WIN!");
+                self.win = true;
+                if !self.keep_playing {
+                    tx.send(&PlayViewOut::Win);
+                }
+            } else {
+                tx.send(&PlayViewOut::Milestone(value_of(self.game.highest)));
+            }
+        }
+
+        if self.game.is_over() {
+            self.game_over = true;
+            self.record();
+            info!("GAME OVER");
+            tx.send(&PlayViewOut::GameOver);
+        }
+        true
+    }
+
+    /// Patches the existing cards so they animate `outcome` instead of being rebuilt.
+    fn send_moved(&mut self, outcome: MoveOutcome, tx: &Transmitter<PlayViewOut>) {
+        for id in self.consumed.drain(..) {
+            if let Some(index) = self.cards.iter().position(|card| *card == id) {
+                self.cards.remove(index);
+                tx.send(&PlayViewOut::Tiles(TilesPatch::Remove(index)));
+            }
+        }
+        self.consumed = outcome.merges.iter().map(|merge| merge.ids[1]).collect();
+
+        if self.game.score > self.best {
+            self.best = self.game.score;
+            tx.send(&PlayViewOut::Best(self.best));
+        }
+
+        let spawned = outcome.spawned;
+        tx.send(&PlayViewOut::Moved(
+            self.last_move,
+            outcome,
+            self.game.score,
+            self.score_add,
+            self.game.highest,
+        ));
+
+        if let Some(spawn) = spawned {
+            self.cards.push(spawn.id);
+            let tile = Tile {
+                id: spawn.id,
+                exp: spawn.exp,
+            };
+            tx.send(&PlayViewOut::Tiles(TilesPatch::Add(tile, spawn.at)));
+        }
+    }
+
+    /// Rebuilds every card from the current board.
+    fn send_reset(&mut self, tx: &Transmitter<PlayViewOut>) {
+        self.cards = card_ids(&self.game.board);
+        self.consumed.clear();
+        tx.send(&PlayViewOut::Reset(
+            self.game.board.clone(),
+            self.game.score,
+            self.game.highest,
+        ));
+    }
+}
+
+/// The snapshot taken before each move of `game`, by replaying it from the start.
+fn rebuild_history(game: &Game) -> Vec<Snapshot> {
+    let start = Game::replay(game.seed, game.rules.clone(), game.start.clone(), &[]);
+    let mut replayed = match start {
+        Ok(replayed) => replayed,
+        Err(err) => {
+            info!("can't rebuild the undo history: {}", err);
+            return Vec::new();
         }
+    };
+    let mut win = false;
+    let mut history = Vec::with_capacity(game.moves.len());
+    for mv in game.moves.iter() {
+        history.push(Snapshot {
+            board: replayed.board.clone(),
+            rng: replayed.rng.clone(),
+            score: replayed.score,
+            highest: replayed.highest,
+            moves: replayed.moves.len(),
+            win,
+            game_over: false,
+        });
+        let previous = replayed.highest;
+        replayed.play(*mv);
+        win = win || (replayed.highest > previous && replayed.highest >= WIN_EXP);
+    }
+    history
+}
+
+fn now() -> f64 {
+    window()
+        .performance()
+        .expect("Performance not supported")
+        .now()
+}
+
+/// Asks `solver` for a move within `budget` milliseconds, on a `BitBoard`
+/// whenever the board fits in one.
+fn search(solver: &Expectimax, board: &Board, budget: f64) -> Option<Move> {
+    let started = now();
+    let budgeted = Budgeted {
+        search: solver,
+        budget,
+        elapsed: || now() - started,
+    };
+    solve(&budgeted, board)
+}
+
+fn hint_class(mv: Move) -> &'static str {
+    match mv {
+        Move::Up => "hint__up",
+        Move::Left => "hint__left",
+        Move::Down => "hint__down",
+        Move::Right => "hint__right",
     }
 }
 
+fn hint_arrow(mv: Move) -> &'static str {
+    match mv {
+        Move::Up => "↑",
+        Move::Left => "←",
+        Move::Down => "↓",
+        Move::Right => "→",
+    }
+}
+
+/// Sends `msg` back to the component after `millis` milliseconds.
+pub fn send_later<T: Clone + 'static>(sub: &Subscriber<T>, millis: i32, msg: T) {
+    let (tx, rx) = txrx();
+    sub.subscribe(&rx);
+    timeout(millis, move || {
+        tx.send(&msg);
+        false
+    });
+}
+
+/// The first connected controller, if any.
+fn read_gamepad() -> Option<PadState> {
+    let pads = window().navigator().get_gamepads().ok()?;
+    let pad = pads
+        .iter()
+        .filter_map(|pad| pad.dyn_into::<Gamepad>().ok())
+        .find(Gamepad::connected)?;
+    Some(PadState {
+        axes: pad.axes().iter().filter_map(|axis| axis.as_f64()).collect(),
+        buttons: pad
+            .buttons()
+            .iter()
+            .filter_map(|button| button.dyn_into::<GamepadButton>().ok())
+            .map(|button| button.pressed())
+            .collect(),
+    })
+}
+
+fn pointer_point(evt: &Event) -> Point {
+    let evt = evt.unchecked_ref::<PointerEvent>();
+    Point {
+        x: evt.client_x() as f64,
+        y: evt.client_y() as f64,
+        t: evt.time_stamp(),
+    }
+}
+
+fn best_score() -> u64 {
+    Stats::load().best_score
+}
+
+fn card_ids(board: &Board) -> Vec<u32> {
+    board.tiles().iter().map(|(_, tile)| tile.id).collect()
+}
+
 impl Component for Play {
     type ModelMsg = PlayModelIn;
     type ViewMsg = PlayViewOut;
@@ -78,116 +650,104 @@ impl Component for Play {
         &mut self,
         msg: &PlayModelIn,
         tx: &Transmitter<PlayViewOut>,
-        _sub: &Subscriber<PlayModelIn>,
+        sub: &Subscriber<PlayModelIn>,
     ) {
         window().focus().unwrap();
-        if !self.game_over {
-            match msg {
-                PlayModelIn::KeyUp(evt) => {
-                    let key = evt
-                        .as_ref()
-                        .expect("No keyboard event")
-                        .unchecked_ref::<KeyboardEvent>()
-                        .key();
-                    let mut last: Grid = *self.grid.last().expect("Grid is empty");
-                    let mut is_direction = true;
-
-                    match key.as_ref() {
-                        "ArrowUp" => {
-                            self.last_move = Some(Move::Up);
-                            let mv = last.move_up();
-                            self.score += mv.0 as u32;
-                            self.score_add = mv.1;
-                            self.highest = mv.2;
-                            info!("mv_score:{}, res_score:{}", mv.0, self.score);
-                        }
-                        "ArrowDown" => {
-                            self.last_move = Some(Move::Down);
-                            let mv = last.move_down();
-                            self.score += mv.0 as u32;
-                            self.score_add = mv.1;
-                            self.highest = mv.2;
-                            info!("mv_score:{}, res_score:{}", mv.0, self.score);
-                        }
-                        "ArrowLeft" => {
-                            self.last_move = Some(Move::Left);
-                            let mv = last.move_left();
-                            self.score += mv.0 as u32;
-                            self.score_add = mv.1;
-                            self.highest = mv.2;
-                            info!("mv_score:{}, res_score:{}", mv.0, self.score);
-                        }
-                        "ArrowRight" => {
-                            self.last_move = Some(Move::Right);
-                            let mv = last.move_right();
-                            self.score += mv.0 as u32;
-                            self.score_add = mv.1;
-                            self.highest = mv.2;
-                            info!("mv_score:{}, res_score:{}", mv.0, self.score);
-                        }
-                        _ => is_direction = false,
+        match msg {
+            PlayModelIn::KeyDown(evt) => {
+                let evt = evt
+                    .as_ref()
+                    .expect("No keyboard event")
+                    .unchecked_ref::<KeyboardEvent>();
+                // Held keys and browser shortcuts are not ours.
+                if evt.repeat() || evt.ctrl_key() || evt.meta_key() || evt.alt_key() {
+                    return;
+                }
+                match self.bindings.command(&evt.key()) {
+                    Some(command) => {
+                        evt.prevent_default();
+                        self.command(command, tx);
                     }
-
-                    if is_direction {
-                        let prev = *self.grid.last().expect("Grid is empty");
-                        if !prev.is_same(last) {
-                            // Check if we can move
-                            last.add_random_2();
-                            self.grid.push(last);
-                            tx.send(&PlayViewOut::Moved(
-                                self.last_move,
-                                last,
-                                self.score,
-                                self.score_add,
-                                self.highest,
-                            ));
-                        } else if last.is_full() {
-                            self.game_over = true;
-                            info!("GAME OVER");
+                    None => return,
+                }
+            }
+            PlayModelIn::Mounted(el) => {
+                let (pad_tx, pad_rx) = txrx();
+                sub.subscribe(&pad_rx);
+                let el = el.clone();
+                let mut pad = PadInput::default();
+                request_animation_frame(move |t| {
+                    if let Some(state) = read_gamepad() {
+                        for command in pad.poll(&state, t) {
+                            pad_tx.send(&PlayModelIn::Command(command));
                         }
                     }
-
-                    if self.highest >= 2048 {
-                        info!("WIN!");
-                        // Win
-                        self.win = true;
-                        // tx.send(&PlayViewOut::Moved(self.last_move, last, self.score, self.score_add, self.highest));
-                        tx.send(&PlayViewOut::Win)
+                    el.is_connected()
+                });
+                return;
+            }
+            PlayModelIn::PointerDown(evt) => {
+                self.touch_start = evt.as_ref().map(pointer_point);
+                return;
+            }
+            PlayModelIn::PointerUp(evt) => {
+                let start = self.touch_start.take();
+                let end = evt.as_ref().map(pointer_point);
+                if let (Some(start), Some(end)) = (start, end) {
+                    if let Some(mv) = Swipe::default().classify(start, end) {
+                        self.input_move(mv, tx);
                     }
                 }
-                PlayModelIn::Restart => {
-                    let new = Play::default();
-                    self.grid = new.grid;
-                    self.last_move = new.last_move;
-                    self.game_over = new.game_over;
-                    tx.send(&PlayViewOut::Moved(
-                        self.last_move,
-                        self.grid.last().expect("Default grid is empty").to_owned(),
-                        0,
-                        0,
-                        0,
-                    ))
+            }
+            PlayModelIn::PointerCancel => {
+                self.touch_start = None;
+                return;
+            }
+            PlayModelIn::Command(command) => self.command(*command, tx),
+            PlayModelIn::ToggleAuto if self.daily.is_some() => return,
+            PlayModelIn::ToggleAuto => {
+                self.auto.on = !self.auto.on;
+                tx.send(&PlayViewOut::Auto(self.auto.on));
+                if self.auto.on {
+                    self.auto.run += 1;
+                    self.auto_step(tx, sub);
                 }
-                &PlayModelIn::Undo => {
-                    if self.grid.len() > 1 {
-                        self.grid.pop().expect("Couldn't undo.");
-                        self.last_move = None;
-                        self.score -= self.score_add as u32;
-                        tx.send(&PlayViewOut::Moved(
-                            self.last_move,
-                            self.grid
-                                .last()
-                                .expect("Couldn't complete undo.")
-                                .to_owned(),
-                            self.score,
-                            0,
-                            self.highest,
-                        ))
-                    }
+            }
+            PlayModelIn::AutoStep(run) => {
+                if self.auto.on && *run == self.auto.run {
+                    self.auto_step(tx, sub);
                 }
-                _ => (),
+            }
+            PlayModelIn::AutoDelay(evt) => {
+                if let Some(input) = evt.as_ref().and_then(|evt| evt.target()) {
+                    let value = input.unchecked_into::<HtmlInputElement>().value();
+                    self.auto.delay = value.parse().unwrap_or(self.auto.delay);
+                }
+                return;
+            }
+            PlayModelIn::KeepPlaying => {
+                self.keep_playing = true;
+                tx.send(&PlayViewOut::KeepPlaying);
+            }
+            PlayModelIn::ShowResults => {
+                // Whatever the player picks on the victory screen, the overlay has had its turn.
+                self.keep_playing = true;
+                self.save();
+                window()
+                    .location()
+                    .set_hash(&String::from(self.win_route()))
+                    .expect("Couldn't open the victory screen");
+                return;
+            }
+            PlayModelIn::Share => {
+                let link = self
+                    .share_link()
+                    .unwrap_or_else(|err| format!("Can't share: {}", err));
+                tx.send(&PlayViewOut::Share(link));
+                return;
             }
         }
+        self.save();
     }
 
     fn view(
@@ -195,13 +755,24 @@ impl Component for Play {
         tx: &Transmitter<PlayModelIn>,
         rx: &Receiver<PlayViewOut>,
     ) -> ViewBuilder<HtmlElement> {
-        let loc_score = self.score;
+        let updates = rx.branch_filter_map(|msg| match msg {
+            PlayViewOut::Moved(_mov, outcome, _score, _score_add, _highest) => {
+                Some(card_updates(outcome))
+            }
+            _ => None,
+        });
+        let patches = rx.branch_filter_map(|msg| match msg {
+            PlayViewOut::Tiles(patch) => Some(patch.clone()),
+            _ => None,
+        });
+        let (reset_updates, reset_patches) = (updates.branch(), patches.branch());
         {
             builder!(
                 <div
                     class="App"
                     tabindex="0"
-                    on:keyup=tx.contra_map(|ev: &Event| PlayModelIn::KeyUp(Some(ev.clone())))
+                    on:keydown=tx.contra_map(|ev: &Event| PlayModelIn::KeyDown(Some(ev.clone())))
+                    post:build=tx.contra_map(|el: &HtmlElement| PlayModelIn::Mounted(el.clone()))
                 >
                 <a class="button green back_home" href="#/">
                         <span class="circle">"←"</span>
@@ -211,54 +782,249 @@ impl Component for Play {
                     <h3>{("", rx.branch_map(|msg| {
                         match msg {
                         PlayViewOut::Win => format!("WIN"),
+                        PlayViewOut::Milestone(value) => format!("{}!", value),
                         _ => format!("")
                     }}))}</h3>
                     <p class="score">
                         {(
-                            "0",
-                            rx.branch_map(move |msg| {
+                            self.game.score.to_string(),
+                            rx.branch_filter_map(move |msg| {
                                 match msg {
-                                    PlayViewOut::Moved(_mov, _grid, score_add, score, _highest) => {
-                                        format!("{}", score_add.to_string())
+                                    PlayViewOut::Moved(_, _, score, _, _)
+                                    | PlayViewOut::Reset(_, score, _) => {
+                                        Some(format!("{}", score.to_string()))
                                      },
-                                     _ => format!("{}", loc_score.to_string())
+                                     _ => None
                                 }
                              })
                         )}
                     </p>
+                    <div class="play__best">
+                        <h2>"Best"</h2>
+                        <p class="score">
+                            {(
+                                self.best.max(self.game.score).to_string(),
+                                rx.branch_filter_map(|msg| match msg {
+                                    PlayViewOut::Best(best) => Some(best.to_string()),
+                                    _ => None,
+                                })
+                            )}
+                        </p>
+                    </div>
                 </div>
-                <main class="wrapper"
-                    patch:children=rx.branch_map(move |msg|{
-                        if let PlayViewOut::Moved(_mov, grid, _score, _score_add, _highest) = msg {
-                        Patch::Replace{value: grid.base_grid_view(), index: 1}.clone()
-                    } else {
-                        window().location().set_hash(&format!("#/win?sc={}", loc_score)).unwrap();
-                        Patch::RemoveAll
-                    }
-                    }
-                    )
+                <main class="wrapper" style=board_style(&self.game.board)
+                    on:pointerdown=tx.contra_map(|ev: &Event| PlayModelIn::PointerDown(Some(ev.clone())))
+                    on:pointerup=tx.contra_map(|ev: &Event| PlayModelIn::PointerUp(Some(ev.clone())))
+                    on:pointercancel=tx.contra_map(|_| PlayModelIn::PointerCancel)
+                    patch:children=rx.branch_filter_map(move |msg|{
+                        match msg {
+                            PlayViewOut::Reset(board, _score, _highest) => {
+                                Some(Patch::Replace{
+                                    value: base_grid_view(board, &reset_updates, &reset_patches),
+                                    index: 1,
+                                })
+                            }
+                            _ => None,
+                        }
+                    })
                 >
-                    {render_board()}
-                    {self.grid.last().expect("App grid empty").base_grid_view()}
+                    {render_board(self.game.board.width, self.game.board.height)}
+                    {base_grid_view(&self.game.board, &updates, &patches)}
+                    <div
+                        class=(
+                            "play__hint",
+                            rx.branch_filter_map(|msg| match msg {
+                                PlayViewOut::Hint(Some(mv)) => {
+                                    Some(format!("play__hint visible {}", hint_class(*mv)))
+                                }
+                                PlayViewOut::Hint(None)
+                                | PlayViewOut::Moved(..)
+                                | PlayViewOut::Reset(..) => Some("play__hint".to_string()),
+                                _ => None,
+                            })
+                        )
+                    >
+                        {(
+                            "",
+                            rx.branch_filter_map(|msg| match msg {
+                                PlayViewOut::Hint(Some(mv)) => Some(hint_arrow(*mv).to_string()),
+                                _ => None,
+                            })
+                        )}
+                    </div>
+                    <div
+                        class=(
+                            if self.game_over { "play__over visible" } else { "play__over" },
+                            rx.branch_filter_map(|msg| match msg {
+                                PlayViewOut::GameOver => Some("play__over visible".to_string()),
+                                PlayViewOut::Reset(..) => Some("play__over".to_string()),
+                                _ => None,
+                            })
+                        )
+                    >
+                        <h2>"Game over"</h2>
+                        <a
+                            title="try again"
+                            class="button green play"
+                            on:click = tx.contra_map(|_| PlayModelIn::Command(Command::Restart))
+                        >
+                            {if self.daily.is_some() { "Results" } else { "Try again" }}
+                        </a>
+                        <a
+                            title="watch this game again"
+                            class="button green play"
+                            href=String::from(Route::Replay { id: None })
+                        >
+                            "Replay"
+                        </a>
+                        <a
+                            title="get a link to this game"
+                            class="button green play"
+                            on:click = tx.contra_map(|_| PlayModelIn::Share)
+                        >
+                            "Share"
+                        </a>
+                    </div>
+                    <div
+                        class=(
+                            if self.win && !self.keep_playing {
+                                "play__over play__win visible"
+                            } else {
+                                "play__over play__win"
+                            },
+                            rx.branch_filter_map(|msg| match msg {
+                                PlayViewOut::Win => Some("play__over play__win visible".to_string()),
+                                PlayViewOut::KeepPlaying | PlayViewOut::Reset(..) => {
+                                    Some("play__over play__win".to_string())
+                                }
+                                _ => None,
+                            })
+                        )
+                    >
+                        <h2>"You win!"</h2>
+                        <a
+                            title="keep playing"
+                            class="button green play"
+                            on:click = tx.contra_map(|_| PlayModelIn::KeepPlaying)
+                        >
+                            "Keep playing"
+                        </a>
+                        <a
+                            title="see results"
+                            class="button green play"
+                            on:click = tx.contra_map(|_| PlayModelIn::ShowResults)
+                        >
+                            "Results"
+                        </a>
+                    </div>
                 </main>
-                <div class="play__bottom">
+                <div class=if self.daily.is_some() { "play__bottom daily" } else { "play__bottom" }>
                     <a
                         class="button green play undo"
                         title="undo"
-                        on:click = tx.contra_map(|_| PlayModelIn::Undo)
+                        on:click = tx.contra_map(|_| PlayModelIn::Command(Command::Undo))
                     >
                         <span class="circle">"⭯"</span>
                     </a>
+                    <a
+                        class="button green play redo"
+                        title="redo"
+                        on:click = tx.contra_map(|_| PlayModelIn::Command(Command::Redo))
+                    >
+                        <span class="circle">"⭮"</span>
+                    </a>
                     <a
                         title="restart"
                         class=" button green play"
-                        on:click = tx.contra_map(|_| PlayModelIn::Restart)
+                        on:click = tx.contra_map(|_| PlayModelIn::Command(Command::Restart))
                     >
                         "Restart"
                     </a>
+                    <a
+                        title="hint"
+                        class="button green play hint"
+                        on:click = tx.contra_map(|_| PlayModelIn::Command(Command::Hint))
+                    >
+                        <span class="circle">"?"</span>
+                    </a>
+                    <a
+                        title="let the AI play"
+                        class="button green play auto"
+                        on:click = tx.contra_map(|_| PlayModelIn::ToggleAuto)
+                    >
+                        {(
+                            "Auto",
+                            rx.branch_filter_map(|msg| match msg {
+                                PlayViewOut::Auto(true) => Some("Stop".to_string()),
+                                PlayViewOut::Auto(false) => Some("Auto".to_string()),
+                                _ => None,
+                            })
+                        )}
+                    </a>
+                    <input
+                        class="play__speed"
+                        type="range"
+                        title="delay between AI moves"
+                        min="0"
+                        max="1000"
+                        step="50"
+                        value=self.auto.delay.to_string()
+                        on:input = tx.contra_map(|ev: &Event| PlayModelIn::AutoDelay(Some(ev.clone())))
+                    />
+                    <a
+                        title="get a link to this position"
+                        class="button green play share"
+                        on:click = tx.contra_map(|_| PlayModelIn::Share)
+                    >
+                        "Share"
+                    </a>
                 </div>
+                <input
+                    class=(
+                        "play__share",
+                        rx.branch_filter_map(|msg| match msg {
+                            PlayViewOut::Share(_) => Some("play__share visible".to_string()),
+                            PlayViewOut::Reset(..) => Some("play__share".to_string()),
+                            _ => None,
+                        })
+                    )
+                    type="text"
+                    readonly="readonly"
+                    value=(
+                        "",
+                        rx.branch_filter_map(|msg| match msg {
+                            PlayViewOut::Share(link) => Some(link.clone()),
+                            _ => None,
+                        })
+                    )
+                />
             </div>
             )
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::MOVES;
+
+    #[test]
+    fn rebuilt_history_matches_the_game() {
+        let mut game = Game::new(3);
+        for mv in MOVES.iter().cycle().take(60) {
+            game.play(*mv);
+        }
+
+        let history = rebuild_history(&game);
+        assert_eq!(history.len(), game.moves.len());
+        for (count, snapshot) in history.iter().enumerate() {
+            let moves = &game.moves[..count];
+            let before = Game::replay(game.seed, game.rules.clone(), None, moves).unwrap();
+            assert_eq!(snapshot.board, before.board);
+            assert_eq!(snapshot.rng, before.rng);
+            assert_eq!(snapshot.score, before.score);
+            assert_eq!(snapshot.moves, count);
+        }
+    }
+}
@@ -0,0 +1,247 @@
+use log::info;
+use mogwai::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::HtmlInputElement;
+
+use crate::components::grid::*;
+use crate::containers::play::send_later;
+use crate::engine::code::game_code;
+use crate::engine::{Game, GameRecord};
+use crate::router::Route;
+
+/// Playback speeds, cycled through by the speed button.
+const SPEEDS: [f64; 5] = [0.5, 1.0, 2.0, 4.0, 8.0];
+
+/// Longest pause between two moves during playback, in recorded milliseconds.
+const MAX_PAUSE: f64 = 1500.0;
+
+#[derive(Clone)]
+pub enum In {
+    Step(isize),
+    Seek(Option<Event>),
+    TogglePlay,
+    Speed,
+    /// A playback tick, ignored unless it belongs to the current run.
+    Tick(u32),
+}
+
+#[derive(Clone)]
+pub enum Out {
+    Board(Game),
+    Playing(bool),
+    Speed(f64),
+}
+
+/// Watches a recorded game, re-simulating its moves through the engine.
+pub struct Replay {
+    pub record: GameRecord,
+    /// The record re-played up to the position shown.
+    pub game: Game,
+    pub playing: bool,
+    /// Bumped whenever playback starts, so ticks of an earlier run are dropped.
+    pub run: u32,
+    /// Index into `SPEEDS`.
+    pub speed: usize,
+}
+
+impl Replay {
+    pub fn new(record: GameRecord) -> Self {
+        Replay {
+            game: record.replay(0),
+            record,
+            playing: false,
+            run: 0,
+            speed: 1,
+        }
+    }
+
+    fn position(&self) -> usize {
+        self.game.moves.len()
+    }
+
+    /// Shows the game after `count` moves, re-playing from the start only
+    /// when going backwards.
+    fn seek(&mut self, count: usize, tx: &Transmitter<Out>) {
+        let count = count.min(self.record.len());
+        if count < self.position() {
+            self.game = self.record.replay(count);
+        }
+        for timed in self.record.moves[self.position()..count].iter() {
+            self.game.play(timed.mv);
+        }
+        tx.send(&Out::Board(self.game.clone()));
+    }
+
+    /// Waits as long as the player did before the next move, sped up.
+    fn schedule(&self, sub: &Subscriber<In>) {
+        let moves = &self.record.moves;
+        let position = self.position();
+        let pause = match (position.checked_sub(1), moves.get(position)) {
+            (Some(previous), Some(next)) => next.at - moves[previous].at,
+            (None, Some(next)) => next.at,
+            _ => 0.0,
+        };
+        let delay = pause.clamp(0.0, MAX_PAUSE) / SPEEDS[self.speed];
+        send_later(sub, delay as i32, In::Tick(self.run));
+    }
+
+    fn set_playing(&mut self, playing: bool, tx: &Transmitter<Out>, sub: &Subscriber<In>) {
+        self.playing = playing;
+        tx.send(&Out::Playing(playing));
+        if playing {
+            self.run += 1;
+            self.schedule(sub);
+        }
+    }
+}
+
+impl Component for Replay {
+    type ModelMsg = In;
+    type ViewMsg = Out;
+    type DomNode = HtmlElement;
+
+    fn update(&mut self, msg: &In, tx: &Transmitter<Out>, sub: &Subscriber<In>) {
+        match msg {
+            In::Step(delta) => {
+                let count = self.position() as isize + delta;
+                self.seek(count.max(0) as usize, tx);
+            }
+            In::Seek(evt) => {
+                if let Some(input) = evt.as_ref().and_then(|evt| evt.target()) {
+                    let value = input.unchecked_into::<HtmlInputElement>().value();
+                    if let Ok(count) = value.parse() {
+                        self.seek(count, tx);
+                    }
+                }
+            }
+            In::TogglePlay => {
+                // Playing from the end starts over.
+                if !self.playing && self.position() == self.record.len() {
+                    self.seek(0, tx);
+                }
+                self.set_playing(!self.playing, tx, sub);
+            }
+            In::Speed => {
+                self.speed = (self.speed + 1) % SPEEDS.len();
+                tx.send(&Out::Speed(SPEEDS[self.speed]));
+            }
+            In::Tick(run) => {
+                if !self.playing || *run != self.run {
+                    return;
+                }
+                if self.position() < self.record.len() {
+                    self.seek(self.position() + 1, tx);
+                    self.schedule(sub);
+                } else {
+                    info!("replay over");
+                    self.set_playing(false, tx, sub);
+                }
+            }
+        }
+    }
+
+    fn view(&self, tx: &Transmitter<In>, rx: &Receiver<Out>) -> ViewBuilder<HtmlElement> {
+        // Positions are replaced wholesale, so the cards never get updates of their own.
+        let (_, updates) = txrx::<Vec<CardUpdate>>();
+        let (_, patches) = txrx::<TilesPatch>();
+        let (reset_updates, reset_patches) = (updates.branch(), patches.branch());
+        let total = self.record.len();
+        // Only games dealt from their seed with the default spawns have a code.
+        let shareable = game_code(&self.record.replay(total)).is_ok();
+        let share = String::from(Route::SharedGame {
+            record: self.record.clone(),
+        });
+
+        builder! {
+            <div class="App replay">
+                <a class="button green back_home" href="#/">
+                    <span class="circle">"←"</span>
+                </a>
+                <div class="play__top">
+                    <h2>"Replay"</h2>
+                    <p class="score">
+                        {(
+                            self.game.score.to_string(),
+                            rx.branch_filter_map(|msg| match msg {
+                                Out::Board(game) => Some(game.score.to_string()),
+                                _ => None,
+                            })
+                        )}
+                    </p>
+                </div>
+                <main class="wrapper" style=board_style(&self.game.board)
+                    patch:children=rx.branch_filter_map(move |msg| match msg {
+                        Out::Board(game) => Some(Patch::Replace {
+                            value: base_grid_view(&game.board, &reset_updates, &reset_patches),
+                            index: 1,
+                        }),
+                        _ => None,
+                    })
+                >
+                    {render_board(self.game.board.width, self.game.board.height)}
+                    {base_grid_view(&self.game.board, &updates, &patches)}
+                </main>
+                <div class="replay__controls">
+                    <input
+                        class="replay__timeline"
+                        type="range"
+                        min="0"
+                        max=total.to_string()
+                        value=(
+                            self.game.moves.len().to_string(),
+                            rx.branch_filter_map(|msg| match msg {
+                                Out::Board(game) => Some(game.moves.len().to_string()),
+                                _ => None,
+                            })
+                        )
+                        on:input=tx.contra_map(|ev: &Event| In::Seek(Some(ev.clone())))
+                    />
+                    <span class="replay__position">
+                        {(
+                            format!("{} / {}", self.game.moves.len(), total),
+                            rx.branch_filter_map(move |msg| match msg {
+                                Out::Board(game) => Some(format!("{} / {}", game.moves.len(), total)),
+                                _ => None,
+                            })
+                        )}
+                    </span>
+                </div>
+                <div class="play__bottom">
+                    <a class="button green play" title="previous move" on:click=tx.contra_map(|_| In::Step(-1))>
+                        "◀"
+                    </a>
+                    <a class="button green play" title="play" on:click=tx.contra_map(|_| In::TogglePlay)>
+                        {(
+                            "Play",
+                            rx.branch_filter_map(|msg| match msg {
+                                Out::Playing(true) => Some("Pause".to_string()),
+                                Out::Playing(false) => Some("Play".to_string()),
+                                _ => None,
+                            })
+                        )}
+                    </a>
+                    <a class="button green play" title="next move" on:click=tx.contra_map(|_| In::Step(1))>
+                        "▶"
+                    </a>
+                    <a class="button green play" title="playback speed" on:click=tx.contra_map(|_| In::Speed)>
+                        {(
+                            format!("{}x", SPEEDS[self.speed]),
+                            rx.branch_filter_map(|msg| match msg {
+                                Out::Speed(speed) => Some(format!("{}x", speed)),
+                                _ => None,
+                            })
+                        )}
+                    </a>
+                    <a
+                        class="button green play"
+                        title="link to this game"
+                        style=if shareable { "" } else { "display: none" }
+                        href=share
+                    >
+                        "Share"
+                    </a>
+                </div>
+            </div>
+        }
+    }
+}
@@ -0,0 +1,158 @@
+use mogwai::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::KeyboardEvent;
+
+use crate::input::{Command, KeyBindings, Preset, COMMANDS, PRESETS};
+
+#[derive(Clone)]
+pub enum In {
+    Preset(Preset),
+    Listen(Command),
+    KeyDown(Option<Event>),
+    Clear(Command),
+}
+
+#[derive(Clone)]
+pub enum Out {
+    /// The keys now bound to a command, ready for display.
+    Keys(Command, String),
+    Listening(Option<Command>),
+}
+
+/// Rebinds the keys of the `Play` screen.
+pub struct Settings {
+    pub bindings: KeyBindings,
+    /// The command waiting for the next key press.
+    pub listening: Option<Command>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            bindings: KeyBindings::load(),
+            listening: None,
+        }
+    }
+}
+
+impl Settings {
+    fn keys_text(&self, command: Command) -> String {
+        let keys = self.bindings.keys_for(command);
+        if keys.is_empty() {
+            "—".to_string()
+        } else {
+            keys.join(", ")
+        }
+    }
+
+    fn send_keys(&self, tx: &Transmitter<Out>) {
+        for command in COMMANDS.iter() {
+            tx.send(&Out::Keys(*command, self.keys_text(*command)));
+        }
+    }
+}
+
+impl Component for Settings {
+    type ModelMsg = In;
+    type ViewMsg = Out;
+    type DomNode = HtmlElement;
+
+    fn update(&mut self, msg: &In, tx: &Transmitter<Out>, _sub: &Subscriber<In>) {
+        match msg {
+            In::Preset(preset) => self.bindings = KeyBindings::preset(*preset),
+            In::Listen(command) => {
+                self.listening = Some(*command);
+                tx.send(&Out::Listening(self.listening));
+                return;
+            }
+            In::KeyDown(evt) => {
+                let command = match self.listening.take() {
+                    Some(command) => command,
+                    None => return,
+                };
+                let evt = evt
+                    .as_ref()
+                    .expect("No keyboard event")
+                    .unchecked_ref::<KeyboardEvent>();
+                evt.prevent_default();
+                tx.send(&Out::Listening(None));
+                // Escape gives up on rebinding.
+                if evt.key() == "Escape" {
+                    return;
+                }
+                self.bindings.bind(&evt.key(), command);
+            }
+            In::Clear(command) => self.bindings.clear(*command),
+        }
+        self.bindings.save();
+        self.send_keys(tx);
+    }
+
+    fn view(&self, tx: &Transmitter<In>, rx: &Receiver<Out>) -> ViewBuilder<HtmlElement> {
+        let mut presets = builder! {
+            <div class="settings__presets"></div>
+        };
+        for preset in PRESETS.iter().copied() {
+            presets.with(builder! {
+                <a class="button green" on:click=tx.contra_map(move |_| In::Preset(preset))>
+                    {preset.name()}
+                </a>
+            });
+        }
+
+        let mut rows = builder! {
+            <tbody></tbody>
+        };
+        for command in COMMANDS.iter().copied() {
+            rows.with(builder! {
+                <tr>
+                    <td>{command.label()}</td>
+                    <td class="settings__keys">
+                        {(
+                            self.keys_text(command),
+                            rx.branch_filter_map(move |msg| match msg {
+                                Out::Keys(bound, keys) if *bound == command => Some(keys.clone()),
+                                _ => None,
+                            })
+                        )}
+                    </td>
+                    <td>
+                        <a class="button green" on:click=tx.contra_map(move |_| In::Listen(command))>
+                            {(
+                                "Add key",
+                                rx.branch_filter_map(move |msg| match msg {
+                                    Out::Listening(Some(listening)) if *listening == command => {
+                                        Some("Press a key".to_string())
+                                    }
+                                    Out::Listening(_) => Some("Add key".to_string()),
+                                    _ => None,
+                                })
+                            )}
+                        </a>
+                    </td>
+                    <td>
+                        <a class="button green" on:click=tx.contra_map(move |_| In::Clear(command))>
+                            "Clear"
+                        </a>
+                    </td>
+                </tr>
+            });
+        }
+
+        builder! {
+            <div
+                class="settings"
+                window:keydown=tx.contra_map(|ev: &Event| In::KeyDown(Some(ev.clone())))
+            >
+                <a class="button green back_home" href="#/">
+                    <span class="circle">"←"</span>
+                </a>
+                <h1>"Controls"</h1>
+                {presets}
+                <table class="settings__bindings">
+                    {rows}
+                </table>
+            </div>
+        }
+    }
+}
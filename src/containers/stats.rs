@@ -0,0 +1,108 @@
+use mogwai::prelude::*;
+
+use crate::engine::value_of;
+use crate::records;
+use crate::router::Route;
+use crate::stats::Stats;
+
+#[derive(Clone)]
+pub enum In {}
+
+#[derive(Clone)]
+pub enum Out {}
+
+pub struct StatsPage {
+    pub stats: Stats,
+}
+
+impl Default for StatsPage {
+    fn default() -> Self {
+        StatsPage {
+            stats: Stats::load(),
+        }
+    }
+}
+
+impl Component for StatsPage {
+    type ModelMsg = In;
+    type ViewMsg = Out;
+    type DomNode = HtmlElement;
+
+    fn update(&mut self, _msg: &In, _tx_view: &Transmitter<Out>, _sub: &Subscriber<In>) {}
+
+    fn view(&self, _tx: &Transmitter<In>, _rx: &Receiver<Out>) -> ViewBuilder<HtmlElement> {
+        let stats = &self.stats;
+        let won = (100 * stats.games_won)
+            .checked_div(stats.games_played)
+            .unwrap_or(0);
+
+        // One bar per highest tile, scaled against the most frequent one.
+        let most = stats.max_tiles.values().copied().max().unwrap_or(1);
+        let mut tiles = builder! {
+            <ul class="stats__tiles"></ul>
+        };
+        for (exp, count) in stats.max_tiles.iter().rev() {
+            tiles.with(builder! {
+                <li>
+                    <span class="stats__tile">{value_of(*exp).to_string()}</span>
+                    <span
+                        class="stats__bar"
+                        style=format!("width: {}%", 100 * count / most)
+                    >
+                        {count.to_string()}
+                    </span>
+                </li>
+            });
+        }
+
+        let mut recent = builder! {
+            <ul class="stats__recent"></ul>
+        };
+        for stored in records::list().into_iter().rev().take(5) {
+            let route = Route::Replay {
+                id: Some(stored.id),
+            };
+            recent.with(builder! {
+                <li>
+                    <a href=String::from(route)>
+                        {format!(
+                            "{} points, {} tile, {} moves",
+                            stored.record.score,
+                            value_of(stored.record.highest),
+                            stored.record.len()
+                        )}
+                    </a>
+                </li>
+            });
+        }
+
+        builder! {
+            <div class="stats">
+                <a class="button green back_home" href="#/">
+                    <span class="circle">"←"</span>
+                </a>
+                <h1>"Statistics"</h1>
+                <dl class="stats__summary">
+                    <dt>"Best score"</dt>
+                    <dd>{stats.best_score.to_string()}</dd>
+                    <dt>"Best tile"</dt>
+                    <dd>{value_of(stats.best_tile).to_string()}</dd>
+                    <dt>"Games played"</dt>
+                    <dd>{stats.games_played.to_string()}</dd>
+                    <dt>"Games won"</dt>
+                    <dd>{format!("{} ({}%)", stats.games_won, won)}</dd>
+                    <dt>"Average score"</dt>
+                    <dd>{stats.average_score().to_string()}</dd>
+                    <dt>"Total moves"</dt>
+                    <dd>{stats.total_moves.to_string()}</dd>
+                    <dt>"Hints used"</dt>
+                    <dd>{stats.hints_used.to_string()}</dd>
+                </dl>
+                <h2>"Highest tile reached"</h2>
+                {tiles}
+                <h2>"Recent games"</h2>
+                {recent}
+            </div>
+        }
+    }
+}
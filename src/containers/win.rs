@@ -0,0 +1,73 @@
+use mogwai::prelude::*;
+
+use crate::containers::play::Play;
+use crate::router::Route;
+
+#[derive(Clone)]
+pub enum In {
+    Continue,
+    NewGame,
+}
+
+#[derive(Clone)]
+pub enum Out {}
+
+pub struct Win {
+    pub score: u64,
+    pub tile: u64,
+    pub moves: usize,
+    pub secs: u64,
+}
+
+impl Component for Win {
+    type ModelMsg = In;
+    type ViewMsg = Out;
+    type DomNode = HtmlElement;
+
+    fn update(&mut self, msg: &In, _tx_view: &Transmitter<Out>, _sub: &Subscriber<In>) {
+        match msg {
+            // The saved game is picked up again by the play route.
+            In::Continue => (),
+            In::NewGame => Play::discard_saved(),
+        }
+        window()
+            .location()
+            .set_hash(&String::from(Route::play()))
+            .expect("Couldn't go back to #/play");
+    }
+
+    fn view(&self, tx: &Transmitter<In>, _rx: &Receiver<Out>) -> ViewBuilder<HtmlElement> {
+        builder! {
+            <div class="win">
+                <div class="bubble main green">
+                    <h1 class="buble__title">"You win!"</h1>
+                    <h2 class="bubble__subtitle">{format!("{} points", self.score)}</h2>
+                </div>
+                <dl class="win__stats">
+                    <dt>"Best tile"</dt>
+                    <dd>{self.tile.to_string()}</dd>
+                    <dt>"Moves"</dt>
+                    <dd>{self.moves.to_string()}</dd>
+                    <dt>"Time"</dt>
+                    <dd>{format!("{}:{:02}", self.secs / 60, self.secs % 60)}</dd>
+                </dl>
+                <div class="win__actions">
+                    <a
+                        class="button green play"
+                        title="continue playing"
+                        on:click=tx.contra_map(|_| In::Continue)
+                    >
+                        "Continue playing"
+                    </a>
+                    <a
+                        class="button green play"
+                        title="new game"
+                        on:click=tx.contra_map(|_| In::NewGame)
+                    >
+                        "New game"
+                    </a>
+                </div>
+            </div>
+        }
+    }
+}
@@ -0,0 +1,172 @@
+//! The daily challenge: one game a day, dealt from a seed everyone shares.
+use mogwai::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::engine::Game;
+use crate::storage;
+
+/// localStorage key of the daily results.
+const DAILY: &str = "2048.daily";
+
+const DAY_MS: f64 = 86_400_000.0;
+
+/// Days since 1970-01-01, in UTC.
+pub fn today() -> i64 {
+    let performance = window().performance().expect("Performance not supported");
+    ((performance.time_origin() + performance.now()) / DAY_MS).floor() as i64
+}
+
+/// The seed of `day`'s game, the same for every player.
+pub fn seed(day: i64) -> u64 {
+    // splitmix64, so that neighbouring days get unrelated spawns
+    let mut z = (day as u64).wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// `day` as `YYYY-MM-DD`.
+pub fn date(day: i64) -> String {
+    // Howard Hinnant's civil_from_days, with years starting in March.
+    let z = day + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y = yoe + era * 400 + if m <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", y, m, d)
+}
+
+/// How the day's single attempt went.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DayResult {
+    pub score: u64,
+    pub highest: u8,
+    pub moves: usize,
+    pub won: bool,
+    /// Id of the game in the recorded games.
+    pub replay: String,
+}
+
+/// Every daily attempt, by day, kept in localStorage.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Daily {
+    pub days: BTreeMap<i64, DayResult>,
+}
+
+impl Daily {
+    pub fn load() -> Daily {
+        storage::load(DAILY).unwrap_or_default()
+    }
+
+    /// Keeps the result of `day`'s game, unless that day was already played.
+    pub fn record(day: i64, game: &Game, won: bool, replay: String) -> Daily {
+        let mut daily = Daily::load();
+        daily.days.entry(day).or_insert(DayResult {
+            score: game.score,
+            highest: game.highest,
+            moves: game.moves.len(),
+            won,
+            replay,
+        });
+        storage::save(DAILY, &daily);
+        daily
+    }
+
+    pub fn result(&self, day: i64) -> Option<&DayResult> {
+        self.days.get(&day)
+    }
+
+    /// Consecutive days played up to `today`, or up to yesterday while
+    /// today's game is still ahead.
+    pub fn streak(&self, today: i64) -> u32 {
+        let mut day = if self.days.contains_key(&today) {
+            today
+        } else {
+            today - 1
+        };
+        let mut streak = 0;
+        while self.days.contains_key(&day) {
+            streak += 1;
+            day -= 1;
+        }
+        streak
+    }
+
+    pub fn best_streak(&self) -> u32 {
+        let mut best = 0;
+        let mut current = 0;
+        let mut previous = None;
+        for day in self.days.keys() {
+            current = if previous == Some(day - 1) {
+                current + 1
+            } else {
+                1
+            };
+            best = best.max(current);
+            previous = Some(*day);
+        }
+        best
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn played(days: &[i64]) -> Daily {
+        let result = DayResult {
+            score: 0,
+            highest: 0,
+            moves: 0,
+            won: false,
+            replay: String::new(),
+        };
+        Daily {
+            days: days.iter().map(|day| (*day, result.clone())).collect(),
+        }
+    }
+
+    #[test]
+    fn dates_cross_month_and_year_boundaries() {
+        for (day, expected) in [
+            (0, "1970-01-01"),
+            (-1, "1969-12-31"),
+            (11_016, "2000-02-29"),
+            (11_017, "2000-03-01"),
+            (20_088, "2024-12-31"),
+            (20_089, "2025-01-01"),
+        ]
+        .iter()
+        {
+            assert_eq!(date(*day), *expected);
+        }
+    }
+
+    #[test]
+    fn seeds_are_splitmix64() {
+        assert_eq!(seed(0), 0xE220_A839_7B1D_CDAF);
+        assert_eq!(seed(20_088), seed(20_088));
+        assert_ne!(seed(20_088), seed(20_089));
+    }
+
+    #[test]
+    fn streaks_stop_at_a_gap() {
+        let daily = played(&[10, 11, 13, 14, 15]);
+        assert_eq!(daily.streak(15), 3);
+        // Today's game may still be ahead.
+        assert_eq!(daily.streak(16), 3);
+        assert_eq!(daily.streak(17), 0);
+        assert_eq!(daily.streak(12), 2);
+        assert_eq!(daily.best_streak(), 3);
+
+        let daily = played(&[1, 2, 3, 4, 10]);
+        assert_eq!(daily.streak(10), 1);
+        assert_eq!(daily.best_streak(), 4);
+        assert_eq!(played(&[]).best_streak(), 0);
+    }
+}
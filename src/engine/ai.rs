@@ -0,0 +1,244 @@
+use std::convert::TryFrom;
+
+use super::bitboard::BitBoard;
+use super::board::{Board, Cell, Move, MOVES};
+use super::position::Position;
+use super::spawn::SpawnPolicy;
+
+/// Weights of the terms a leaf position is judged by.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Heuristic {
+    /// Per empty cell.
+    pub empty: f64,
+    /// Per step of tiles going against the best ordering of each line.
+    pub monotonicity: f64,
+    /// Per step of difference between neighbouring tiles.
+    pub smoothness: f64,
+    /// Per exponent of the highest tile, when it sits in a corner.
+    pub corner: f64,
+}
+
+impl Default for Heuristic {
+    fn default() -> Self {
+        Heuristic {
+            empty: 2.7,
+            monotonicity: 1.0,
+            smoothness: 0.1,
+            corner: 1.0,
+        }
+    }
+}
+
+impl Heuristic {
+    pub fn evaluate<P: Position>(&self, pos: &P) -> f64 {
+        let (width, height) = (pos.width(), pos.height());
+        let rows = (0..height).map(|row| (0..width).map(|col| (row, col)).collect());
+        let cols = (0..width).map(|col| (0..height).map(|row| (row, col)).collect());
+
+        let mut monotonicity = 0.0;
+        let mut smoothness = 0.0;
+        for line in rows.chain(cols).collect::<Vec<Vec<Cell>>>() {
+            let exps = line
+                .iter()
+                .map(|at| pos.exp(*at) as f64)
+                .collect::<Vec<_>>();
+            let (mut up, mut down) = (0.0, 0.0);
+            for pair in exps.windows(2) {
+                if pair[0] > pair[1] {
+                    down += pair[0] - pair[1];
+                } else {
+                    up += pair[1] - pair[0];
+                }
+                if pair[0] != 0.0 && pair[1] != 0.0 {
+                    smoothness += (pair[0] - pair[1]).abs();
+                }
+            }
+            monotonicity += f64::min(up, down);
+        }
+
+        let highest = pos.highest();
+        let corners = [
+            (0, 0),
+            (0, width - 1),
+            (height - 1, 0),
+            (height - 1, width - 1),
+        ];
+        let cornered = corners.iter().any(|at| pos.exp(*at) == highest);
+
+        self.empty * pos.empty_cells().len() as f64
+            - self.monotonicity * monotonicity
+            - self.smoothness * smoothness
+            + if cornered {
+                self.corner * highest as f64
+            } else {
+                0.0
+            }
+    }
+}
+
+/// Value of a position with no legal move left.
+const LOST: f64 = -1.0e4;
+
+/// Chance branches less likely than this are judged by the heuristic
+/// instead of being searched.
+const MIN_PROBABILITY: f64 = 1.0e-4;
+
+/// Looks `depth` player moves ahead, averaging over every spawn the
+/// `spawn` policy could produce in between.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Expectimax {
+    pub depth: u32,
+    pub heuristic: Heuristic,
+    pub spawn: SpawnPolicy,
+}
+
+impl Default for Expectimax {
+    fn default() -> Self {
+        Expectimax::new(3)
+    }
+}
+
+impl Expectimax {
+    pub fn new(depth: u32) -> Self {
+        Expectimax {
+            depth,
+            heuristic: Heuristic::default(),
+            spawn: SpawnPolicy::default(),
+        }
+    }
+
+    pub fn with_spawn(self, spawn: SpawnPolicy) -> Self {
+        Expectimax { spawn, ..self }
+    }
+
+    /// The move with the best expected value, `None` when the game is over.
+    pub fn best_move<P: Position>(&self, pos: &P) -> Option<Move> {
+        self.rank_moves(pos).first().map(|(mv, _)| *mv)
+    }
+
+    /// Searches one level deeper at a time, up to `depth`, for as long as the
+    /// next level is likely to finish within `budget`. `elapsed` reports the
+    /// milliseconds spent so far, since the engine has no clock of its own.
+    pub fn best_move_within<P, F>(&self, pos: &P, budget: f64, mut elapsed: F) -> Option<Move>
+    where
+        P: Position,
+        F: FnMut() -> f64,
+    {
+        let mut best = None;
+        for depth in 1..=self.depth.max(1) {
+            let started = elapsed();
+            let search = Expectimax {
+                depth,
+                ..self.clone()
+            };
+            best = Some(search.best_move(pos)?);
+
+            // Every extra level costs roughly ten times the one before.
+            let took = elapsed() - started;
+            if elapsed() + 10.0 * took > budget {
+                break;
+            }
+        }
+        best
+    }
+
+    /// Every legal move with its expected value, best first.
+    pub fn rank_moves<P: Position>(&self, pos: &P) -> Vec<(Move, f64)> {
+        let mut ranked = MOVES
+            .iter()
+            .filter_map(|mv| {
+                let mut next = pos.clone();
+                next.slide(*mv)?;
+                let depth = self.depth.saturating_sub(1);
+                Some((*mv, self.chance(&next, depth, 1.0)))
+            })
+            .collect::<Vec<_>>();
+        ranked.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        ranked
+    }
+
+    /// Expected value over the tile spawned next, with `depth` moves left to search.
+    fn chance<P: Position>(&self, pos: &P, depth: u32, probability: f64) -> f64 {
+        let empty = pos.empty_cells();
+        if depth == 0 || empty.is_empty() || probability < MIN_PROBABILITY {
+            return self.heuristic.evaluate(pos);
+        }
+
+        let spawns = self.spawn.probabilities();
+        let mut value = 0.0;
+        for at in empty.iter() {
+            for (exp, p) in spawns.iter().copied() {
+                let mut next = pos.clone();
+                next.set(*at, exp);
+                value += p * self.max(&next, depth, probability * p / empty.len() as f64);
+            }
+        }
+        value / empty.len() as f64
+    }
+
+    fn max<P: Position>(&self, pos: &P, depth: u32, probability: f64) -> f64 {
+        MOVES
+            .iter()
+            .filter_map(|mv| {
+                let mut next = pos.clone();
+                next.slide(*mv)?;
+                Some(self.chance(&next, depth - 1, probability))
+            })
+            .fold(LOST, f64::max)
+    }
+}
+
+/// Anything that picks a move on any `Position`, so it can be run on
+/// whichever representation fits the board.
+pub trait Solver {
+    fn solve<P: Position>(&self, pos: &P) -> Option<Move>;
+}
+
+impl Solver for Expectimax {
+    fn solve<P: Position>(&self, pos: &P) -> Option<Move> {
+        self.best_move(pos)
+    }
+}
+
+/// A search held to `budget` milliseconds, see `Expectimax::best_move_within`.
+pub struct Budgeted<'a, F> {
+    pub search: &'a Expectimax,
+    pub budget: f64,
+    pub elapsed: F,
+}
+
+impl<F: Fn() -> f64> Solver for Budgeted<'_, F> {
+    fn solve<P: Position>(&self, pos: &P) -> Option<Move> {
+        self.search
+            .best_move_within(pos, self.budget, &self.elapsed)
+    }
+}
+
+/// Runs `solver` on a `BitBoard` when `board` fits in one, on `board` itself otherwise.
+pub fn solve<S: Solver>(solver: &S, board: &Board) -> Option<Move> {
+    match BitBoard::try_from(board) {
+        Ok(bits) => solver.solve(&bits),
+        Err(_) => solver.solve(board),
+    }
+}
+
+/// The expectimax move for any board.
+pub fn best_move(board: &Board, depth: u32) -> Option<Move> {
+    solve(&Expectimax::new(depth), board)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zero_spawn_weights_still_rank_moves() {
+        let mut board = Board::new(4, 4);
+        board.insert((0, 0), 1);
+        board.insert((0, 1), 2);
+        let search = Expectimax::new(2).with_spawn(SpawnPolicy::new(vec![(1, 0)], 2));
+        let ranked = search.rank_moves(&board);
+        assert!(!ranked.is_empty());
+        assert!(ranked.iter().all(|(_, value)| value.is_finite()));
+    }
+}
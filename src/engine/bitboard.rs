@@ -0,0 +1,269 @@
+use std::convert::TryFrom;
+
+use lazy_static::*;
+
+use super::board::{value_of, Board, Cell, Move};
+use super::Position;
+
+/// Highest exponent that fits in a nibble; two such tiles never merge, so
+/// boards already holding one are refused.
+pub const MAX_EXP: u8 = 15;
+
+/// A 4x4 board packed in a `u64`, 4 bits of exponent per cell. Row `r` lives
+/// in bits `16 * r..16 * (r + 1)` with column 0 in the lowest nibble.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct BitBoard(pub u64);
+
+struct RowTables {
+    left: Vec<u16>,
+    right: Vec<u16>,
+    score: Vec<u64>,
+}
+
+lazy_static! {
+    static ref ROWS: RowTables = RowTables::new();
+}
+
+impl RowTables {
+    fn new() -> Self {
+        let mut tables = RowTables {
+            left: vec![0; 1 << 16],
+            right: vec![0; 1 << 16],
+            score: vec![0; 1 << 16],
+        };
+
+        for row in 0..=u16::MAX {
+            let mut line = unpack_row(row);
+            let score = slide_line(&mut line);
+            tables.left[row as usize] = pack_row(line);
+            tables.score[row as usize] = score;
+
+            let mut line = unpack_row(row);
+            line.reverse();
+            slide_line(&mut line);
+            line.reverse();
+            tables.right[row as usize] = pack_row(line);
+        }
+        tables
+    }
+}
+
+fn unpack_row(row: u16) -> [u8; 4] {
+    let mut line = [0; 4];
+    for (col, exp) in line.iter_mut().enumerate() {
+        *exp = ((row >> (4 * col)) & 0xF) as u8;
+    }
+    line
+}
+
+fn pack_row(line: [u8; 4]) -> u16 {
+    line.iter()
+        .enumerate()
+        .fold(0, |row, (col, exp)| row | (*exp as u16) << (4 * col))
+}
+
+/// Slides a line of exponents towards index 0, returning the points gained.
+fn slide_line(line: &mut [u8; 4]) -> u64 {
+    let mut out = [0; 4];
+    let mut target = 0;
+    let mut score = 0;
+    let mut pending = 0;
+
+    for exp in line.iter().copied().filter(|exp| *exp != 0) {
+        if pending == exp && exp < MAX_EXP {
+            out[target] = exp + 1;
+            score += value_of(exp + 1);
+            pending = 0;
+            target += 1;
+        } else {
+            if pending != 0 {
+                out[target] = pending;
+                target += 1;
+            }
+            pending = exp;
+        }
+    }
+    if pending != 0 {
+        out[target] = pending;
+    }
+
+    *line = out;
+    score
+}
+
+fn transpose(x: u64) -> u64 {
+    let a1 = x & 0xF0F0_0F0F_F0F0_0F0F;
+    let a2 = x & 0x0000_F0F0_0000_F0F0;
+    let a3 = x & 0x0F0F_0000_0F0F_0000;
+    let a = a1 | (a2 << 12) | (a3 >> 12);
+    let b1 = a & 0xFF00_FF00_00FF_00FF;
+    let b2 = a & 0x00FF_00FF_0000_0000;
+    let b3 = a & 0x0000_0000_FF00_FF00;
+    b1 | (b2 >> 24) | (b3 << 24)
+}
+
+impl BitBoard {
+    /// Moves every row through `table`, returning the new board and the points gained.
+    fn slide_rows(x: u64, table: &[u16]) -> (u64, u64) {
+        let mut out = 0;
+        let mut score = 0;
+        for row in 0..4 {
+            let line = ((x >> (16 * row)) & 0xFFFF) as usize;
+            out |= (table[line] as u64) << (16 * row);
+            score += ROWS.score[line];
+        }
+        (out, score)
+    }
+
+    /// The board after `mv` and the points it scored, without spawning.
+    pub fn moved(self, mv: Move) -> (BitBoard, u64) {
+        let (board, score) = match mv {
+            Move::Left => BitBoard::slide_rows(self.0, &ROWS.left),
+            Move::Right => BitBoard::slide_rows(self.0, &ROWS.right),
+            Move::Up => {
+                let (board, score) = BitBoard::slide_rows(transpose(self.0), &ROWS.left);
+                (transpose(board), score)
+            }
+            Move::Down => {
+                let (board, score) = BitBoard::slide_rows(transpose(self.0), &ROWS.right);
+                (transpose(board), score)
+            }
+        };
+        (BitBoard(board), score)
+    }
+
+    pub fn count_empty(self) -> usize {
+        (0..16).filter(|i| (self.0 >> (4 * i)) & 0xF == 0).count()
+    }
+}
+
+impl Position for BitBoard {
+    fn slide(&mut self, mv: Move) -> Option<u64> {
+        let (next, score) = self.moved(mv);
+        if next == *self {
+            None
+        } else {
+            *self = next;
+            Some(score)
+        }
+    }
+
+    fn exp(&self, at: Cell) -> u8 {
+        ((self.0 >> (4 * (4 * at.0 + at.1))) & 0xF) as u8
+    }
+
+    fn set(&mut self, at: Cell, exp: u8) {
+        let shift = 4 * (4 * at.0 + at.1);
+        self.0 = (self.0 & !(0xF << shift)) | ((exp.min(MAX_EXP) as u64) << shift);
+    }
+
+    fn empty_cells(&self) -> Vec<Cell> {
+        (0..16)
+            .filter(|i| (self.0 >> (4 * i)) & 0xF == 0)
+            .map(|i| (i / 4, i % 4))
+            .collect()
+    }
+
+    fn highest(&self) -> u8 {
+        (0..16)
+            .map(|i| ((self.0 >> (4 * i)) & 0xF) as u8)
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn width(&self) -> usize {
+        4
+    }
+
+    fn height(&self) -> usize {
+        4
+    }
+
+    fn can_move(&self, mv: Move) -> bool {
+        self.moved(mv).0 != *self
+    }
+}
+
+impl TryFrom<&Board> for BitBoard {
+    type Error = String;
+
+    fn try_from(board: &Board) -> Result<BitBoard, String> {
+        if board.width != 4 || board.height != 4 {
+            return Err(format!(
+                "bitboards are 4x4, got {}x{}",
+                board.width, board.height
+            ));
+        }
+
+        let mut bits = BitBoard::default();
+        for (cell, tile) in board.tiles() {
+            if tile.exp >= MAX_EXP {
+                return Err(format!("tile 2^{} does not fit in a bitboard", tile.exp));
+            }
+            bits.set(cell, tile.exp);
+        }
+        Ok(bits)
+    }
+}
+
+impl From<BitBoard> for Board {
+    fn from(bits: BitBoard) -> Board {
+        let mut board = Board::new(4, 4);
+        for row in 0..4 {
+            for col in 0..4 {
+                let exp = bits.exp((row, col));
+                if exp != 0 {
+                    board.insert((row, col), exp);
+                }
+            }
+        }
+        board
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::board::MOVES;
+    use rand::{Rng, SeedableRng};
+    use rand_pcg::Pcg32;
+
+    #[test]
+    fn moves_match_board() {
+        let mut rng = Pcg32::seed_from_u64(0);
+        for _ in 0..1000 {
+            let mut board = Board::new(4, 4);
+            for row in 0..4 {
+                for col in 0..4 {
+                    // Plenty of gaps and small tiles, so that lines merge often.
+                    let exp = rng
+                        .gen_range(0..MAX_EXP)
+                        .saturating_sub(rng.gen_range(0..8));
+                    if exp != 0 {
+                        board.insert((row, col), exp);
+                    }
+                }
+            }
+            let bits = BitBoard::try_from(&board).unwrap();
+
+            for mv in MOVES.iter() {
+                let mut expected = board.clone();
+                let outcome = expected.apply(*mv);
+                let (moved, score) = bits.moved(*mv);
+                assert_eq!(score, outcome.score);
+                for row in 0..4 {
+                    for col in 0..4 {
+                        assert_eq!(moved.exp((row, col)), expected.exp((row, col)));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn refuses_tiles_that_could_not_merge() {
+        let mut board = Board::new(4, 4);
+        board.insert((0, 0), MAX_EXP);
+        assert!(BitBoard::try_from(&board).is_err());
+    }
+}
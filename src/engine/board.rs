@@ -0,0 +1,342 @@
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use super::spawn::SpawnPolicy;
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Move {
+    Up,
+    Left,
+    Down,
+    Right,
+}
+
+pub const MOVES: [Move; 4] = [Move::Up, Move::Left, Move::Down, Move::Right];
+
+/// A `(row, col)` position on the board.
+pub type Cell = (usize, usize);
+
+/// A tile keeps its `id` for as long as it stays on the board, so views can
+/// follow it from cell to cell. Its face value is `2^exp`.
+#[derive(Copy, Clone, Debug, PartialEq, Hash, Serialize, Deserialize)]
+pub struct Tile {
+    pub id: u32,
+    pub exp: u8,
+}
+
+impl Tile {
+    pub fn value(&self) -> u64 {
+        value_of(self.exp)
+    }
+}
+
+/// The face value of a tile with exponent `exp`, or 0 for an empty cell.
+pub fn value_of(exp: u8) -> u64 {
+    if exp == 0 {
+        0
+    } else {
+        1 << exp
+    }
+}
+
+/// A tile that travelled without merging.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Slide {
+    pub id: u32,
+    pub from: Cell,
+    pub to: Cell,
+    pub exp: u8,
+}
+
+/// Two tiles that fused into one; `exp` is the resulting tile, which keeps
+/// the id of the first source while the second one leaves the board.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Merge {
+    pub ids: [u32; 2],
+    pub from: [Cell; 2],
+    pub to: Cell,
+    pub exp: u8,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Spawn {
+    pub id: u32,
+    pub at: Cell,
+    pub exp: u8,
+}
+
+/// What a single move did to the board.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MoveOutcome {
+    /// Whether any tile slid or merged.
+    pub changed: bool,
+    /// Points gained by every merge of this move.
+    pub score: u64,
+    /// Exponent of the highest tile on the board after the move.
+    pub highest: u8,
+    pub slides: Vec<Slide>,
+    pub merges: Vec<Merge>,
+    /// The tile placed after the move, filled in by `Game::play`.
+    pub spawned: Option<Spawn>,
+}
+
+/// A `width` x `height` grid of tiles, stored row by row.
+#[derive(Clone, Debug, PartialEq, Hash, Serialize, Deserialize)]
+pub struct Board {
+    pub width: usize,
+    pub height: usize,
+    pub cells: Vec<Option<Tile>>,
+    pub next_id: u32,
+}
+
+impl Default for Board {
+    fn default() -> Self {
+        Board::new(4, 4)
+    }
+}
+
+impl Board {
+    pub fn new(width: usize, height: usize) -> Self {
+        Board {
+            width,
+            height,
+            cells: vec![None; width * height],
+            next_id: 0,
+        }
+    }
+
+    /// Places a tile drawn from `policy` on a uniformly chosen empty cell.
+    pub fn add_random_tile<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        policy: &SpawnPolicy,
+    ) -> Option<Spawn> {
+        let at = *self.empty_cells().choose(rng)?;
+        let tile = self.insert(at, policy.pick(rng));
+        Some(Spawn {
+            id: tile.id,
+            at,
+            exp: tile.exp,
+        })
+    }
+
+    /// Puts a brand new `2^exp` tile on `at`, replacing whatever was there.
+    pub fn insert(&mut self, at: Cell, exp: u8) -> Tile {
+        let tile = Tile {
+            id: self.next_id,
+            exp,
+        };
+        self.next_id += 1;
+        let index = self.index(at);
+        self.cells[index] = Some(tile);
+        tile
+    }
+
+    pub fn remove(&mut self, at: Cell) -> Option<Tile> {
+        let index = self.index(at);
+        self.cells[index].take()
+    }
+
+    pub fn get(&self, at: Cell) -> Option<Tile> {
+        self.cells[self.index(at)]
+    }
+
+    /// Exponent of the tile on `at`, 0 when the cell is empty.
+    pub fn exp(&self, at: Cell) -> u8 {
+        self.get(at).map_or(0, |tile| tile.exp)
+    }
+
+    pub fn value(&self, at: Cell) -> u64 {
+        value_of(self.exp(at))
+    }
+
+    /// Every tile on the board with its cell, in row-major order.
+    pub fn tiles(&self) -> Vec<(Cell, Tile)> {
+        self.cells
+            .iter()
+            .enumerate()
+            .filter_map(|(index, elem)| elem.map(|tile| (self.cell(index), tile)))
+            .collect()
+    }
+
+    pub fn apply(&mut self, mv: Move) -> MoveOutcome {
+        let mut outcome = MoveOutcome::default();
+        let mut next = vec![None; self.cells.len()];
+
+        for cells in self.lines(mv) {
+            let mut target = 0;
+            // A tile waiting at `cells[target]` that the next one may still merge into.
+            let mut pending: Option<(Cell, Tile)> = None;
+
+            for &from in cells.iter() {
+                let tile = match self.get(from) {
+                    Some(tile) => tile,
+                    None => continue,
+                };
+
+                match pending {
+                    Some((other_from, other)) if other.exp == tile.exp => {
+                        let to = cells[target];
+                        let exp = tile.exp + 1;
+                        next[self.index(to)] = Some(Tile { id: other.id, exp });
+                        outcome.score += value_of(exp);
+                        outcome.merges.push(Merge {
+                            ids: [other.id, tile.id],
+                            from: [other_from, from],
+                            to,
+                            exp,
+                        });
+                        pending = None;
+                        target += 1;
+                    }
+                    Some((other_from, other)) => {
+                        self.place(&mut next, &mut outcome, other_from, cells[target], other);
+                        pending = Some((from, tile));
+                        target += 1;
+                    }
+                    None => pending = Some((from, tile)),
+                }
+            }
+
+            if let Some((other_from, other)) = pending {
+                self.place(&mut next, &mut outcome, other_from, cells[target], other);
+            }
+        }
+
+        self.cells = next;
+        outcome.changed = !outcome.slides.is_empty() || !outcome.merges.is_empty();
+        outcome.highest = self.highest();
+        outcome
+    }
+
+    /// Simulates `mv` on a copy of the board and reports whether any tile would change.
+    pub fn can_move(&self, mv: Move) -> bool {
+        let mut next = self.clone();
+        next.apply(mv).changed
+    }
+
+    pub fn legal_moves(&self) -> Vec<Move> {
+        MOVES
+            .iter()
+            .copied()
+            .filter(|mv| self.can_move(*mv))
+            .collect()
+    }
+
+    pub fn empty_cells(&self) -> Vec<Cell> {
+        self.cells
+            .iter()
+            .enumerate()
+            .filter(|(_, elem)| elem.is_none())
+            .map(|(index, _)| self.cell(index))
+            .collect()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.cells.iter().all(Option::is_some)
+    }
+
+    /// Exponent of the highest tile, 0 on an empty board.
+    pub fn highest(&self) -> u8 {
+        self.cells
+            .iter()
+            .filter_map(|elem| elem.map(|tile| tile.exp))
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn index(&self, at: Cell) -> usize {
+        at.0 * self.width + at.1
+    }
+
+    fn cell(&self, index: usize) -> Cell {
+        (index / self.width, index % self.width)
+    }
+
+    /// Every row or column touched by `mv`, each ordered from the edge the
+    /// tiles slide towards.
+    fn lines(&self, mv: Move) -> Vec<Vec<Cell>> {
+        let (width, height) = (self.width, self.height);
+        match mv {
+            Move::Up => (0..width)
+                .map(|col| (0..height).map(|row| (row, col)).collect())
+                .collect(),
+            Move::Down => (0..width)
+                .map(|col| (0..height).rev().map(|row| (row, col)).collect())
+                .collect(),
+            Move::Left => (0..height)
+                .map(|row| (0..width).map(|col| (row, col)).collect())
+                .collect(),
+            Move::Right => (0..height)
+                .map(|row| (0..width).rev().map(|col| (row, col)).collect())
+                .collect(),
+        }
+    }
+
+    fn place(
+        &self,
+        next: &mut [Option<Tile>],
+        outcome: &mut MoveOutcome,
+        from: Cell,
+        to: Cell,
+        tile: Tile,
+    ) {
+        next[self.index(to)] = Some(tile);
+        if from != to {
+            outcome.slides.push(Slide {
+                id: tile.id,
+                from,
+                to,
+                exp: tile.exp,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 4x4 board with `exps` on its top row, 0 for empty.
+    fn row(exps: &[u8]) -> Board {
+        let mut board = Board::new(4, 4);
+        for (col, exp) in exps.iter().enumerate() {
+            if *exp != 0 {
+                board.insert((0, col), *exp);
+            }
+        }
+        board
+    }
+
+    fn top_row(board: &Board) -> Vec<u8> {
+        (0..board.width).map(|col| board.exp((0, col))).collect()
+    }
+
+    #[test]
+    fn pairs_merge_once_each() {
+        let mut board = row(&[1, 1, 1, 1]);
+        let outcome = board.apply(Move::Left);
+        assert_eq!(top_row(&board), vec![2, 2, 0, 0]);
+        assert_eq!(outcome.score, 8);
+        assert_eq!(outcome.merges.len(), 2);
+    }
+
+    #[test]
+    fn merged_tiles_do_not_merge_again() {
+        let mut board = row(&[1, 1, 2, 0]);
+        board.apply(Move::Right);
+        assert_eq!(top_row(&board), vec![0, 0, 2, 2]);
+    }
+
+    #[test]
+    fn full_checkerboard_has_no_moves() {
+        let mut board = Board::new(4, 4);
+        for r in 0..4 {
+            for c in 0..4 {
+                board.insert((r, c), if (r + c) % 2 == 0 { 1 } else { 2 });
+            }
+        }
+        assert!(board.legal_moves().is_empty());
+    }
+}
@@ -0,0 +1,323 @@
+//! Compact, URL-safe share codes for a position or a whole game.
+//!
+//! Both start with one byte holding the board width and height. A board
+//! code follows with one nibble per cell exponent; a game code with the
+//! seed (8 bytes), the move count (LEB128) and 2 bits per move. Everything
+//! is written in unpadded base64url. Games always use the default spawn
+//! policy.
+use super::bitboard::MAX_EXP;
+use super::board::{Board, Move, MOVES};
+use super::game::Game;
+use super::record::GameRecord;
+use super::rules::Rules;
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+fn base64_encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity((bytes.len() * 4).div_ceil(3));
+    for chunk in bytes.chunks(3) {
+        let block = chunk.iter().enumerate().fold(0u32, |block, (i, byte)| {
+            block | (*byte as u32) << (16 - 8 * i)
+        });
+        for i in 0..=chunk.len() {
+            out.push(ALPHABET[(block >> (18 - 6 * i)) as usize & 0x3F] as char);
+        }
+    }
+    out
+}
+
+fn base64_decode(code: &str) -> Result<Vec<u8>, String> {
+    let digits = code
+        .bytes()
+        .map(|c| {
+            ALPHABET
+                .iter()
+                .position(|a| *a == c)
+                .map(|digit| digit as u32)
+                .ok_or_else(|| format!("invalid character {:?} in code", c as char))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if digits.len() % 4 == 1 {
+        return Err("code has a truncated character".to_string());
+    }
+
+    let mut out = Vec::with_capacity(digits.len() * 3 / 4);
+    for chunk in digits.chunks(4) {
+        let block = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |block, (i, digit)| block | digit << (18 - 6 * i));
+        for i in 0..chunk.len() - 1 {
+            out.push((block >> (16 - 8 * i)) as u8);
+        }
+    }
+    Ok(out)
+}
+
+/// Reads the bytes of a code one field at a time.
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [u8], String> {
+        if self.bytes.len() < count {
+            return Err("code is too short".to_string());
+        }
+        let (taken, rest) = self.bytes.split_at(count);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn rules(&mut self) -> Result<Rules, String> {
+        let size = self.take(1)?[0];
+        Rules::new((size >> 4) as usize, (size & 0xF) as usize)
+    }
+
+    fn varint(&mut self) -> Result<u64, String> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.take(1)?[0];
+            value |= ((byte & 0x7F) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err("code has an overlong number".to_string())
+    }
+
+    fn finish(&self) -> Result<(), String> {
+        if self.bytes.is_empty() {
+            Ok(())
+        } else {
+            Err("code has trailing data".to_string())
+        }
+    }
+}
+
+fn size_byte(width: usize, height: usize) -> u8 {
+    (width as u8) << 4 | height as u8
+}
+
+pub fn board_code(board: &Board) -> Result<String, String> {
+    let mut bytes = vec![size_byte(board.width, board.height)];
+    let exps = (0..board.height)
+        .flat_map(|row| (0..board.width).map(move |col| (row, col)))
+        .map(|at| board.exp(at))
+        .collect::<Vec<_>>();
+    if let Some(exp) = exps.iter().find(|exp| **exp > MAX_EXP) {
+        return Err(format!("tile 2^{} is too big for a code", exp));
+    }
+    for pair in exps.chunks(2) {
+        bytes.push(pair[0] << 4 | pair.get(1).copied().unwrap_or(0));
+    }
+    Ok(base64_encode(&bytes))
+}
+
+/// Decodes a board code into a board that can still be played.
+pub fn board_from_code(code: &str) -> Result<Board, String> {
+    let bytes = base64_decode(code)?;
+    let mut reader = Reader { bytes: &bytes };
+    let rules = reader.rules()?;
+    let cells = rules.width * rules.height;
+    let packed = reader.take(cells.div_ceil(2))?;
+    reader.finish()?;
+
+    let mut board = Board::new(rules.width, rules.height);
+    for index in 0..cells {
+        let exp = (packed[index / 2] >> (4 * (1 - index % 2))) & 0xF;
+        if exp != 0 {
+            board.insert((index / rules.width, index % rules.width), exp);
+        }
+    }
+    // Game over is only noticed after a move, so a board that can't move
+    // would never end.
+    if board.legal_moves().is_empty() {
+        return Err("code holds a board with no move left".to_string());
+    }
+    Ok(board)
+}
+
+pub fn game_code(game: &Game) -> Result<String, String> {
+    if game.start.is_some() {
+        return Err("games started from a shared board have no code".to_string());
+    }
+    if game.rules != Rules::new(game.rules.width, game.rules.height)? {
+        return Err("only games with the default spawns have a code".to_string());
+    }
+
+    let mut bytes = vec![size_byte(game.rules.width, game.rules.height)];
+    bytes.extend_from_slice(&game.seed.to_le_bytes());
+    let mut count = game.moves.len() as u64;
+    loop {
+        let byte = (count & 0x7F) as u8;
+        count >>= 7;
+        if count == 0 {
+            bytes.push(byte);
+            break;
+        }
+        bytes.push(byte | 0x80);
+    }
+    for four in game.moves.chunks(4) {
+        let byte = four.iter().enumerate().fold(0, |byte, (i, mv)| {
+            let index = MOVES.iter().position(|m| m == mv).unwrap_or(0) as u8;
+            byte | index << (6 - 2 * i)
+        });
+        bytes.push(byte);
+    }
+    Ok(base64_encode(&bytes))
+}
+
+/// Decodes a game code and re-plays it into a record.
+pub fn record_from_code(code: &str) -> Result<GameRecord, String> {
+    let bytes = base64_decode(code)?;
+    let mut reader = Reader { bytes: &bytes };
+    let rules = reader.rules()?;
+    let mut seed = [0; 8];
+    seed.copy_from_slice(reader.take(8)?);
+    let count = reader.varint()? as usize;
+    let packed = reader.take(count.div_ceil(4))?;
+    reader.finish()?;
+
+    let moves = (0..count)
+        .map(|index| MOVES[(packed[index / 4] >> (6 - 2 * (index % 4))) as usize & 0x3])
+        .collect::<Vec<Move>>();
+    let game = Game::replay(u64::from_le_bytes(seed), rules, None, &moves)?;
+    if game.moves.len() != moves.len() {
+        return Err("code has moves that change nothing".to_string());
+    }
+    Ok(GameRecord::new(&game, "shared", &[]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exps(board: &Board) -> Vec<u8> {
+        (0..board.height)
+            .flat_map(|row| (0..board.width).map(move |col| (row, col)))
+            .map(|at| board.exp(at))
+            .collect()
+    }
+
+    /// A game on a `width` x `height` board after up to `count` moves.
+    fn played(width: usize, height: usize, count: usize) -> Game {
+        let mut game = Game::with_rules(5, Rules::new(width, height).unwrap());
+        for mv in MOVES.iter().cycle().take(count) {
+            game.play(*mv);
+        }
+        game
+    }
+
+    #[test]
+    fn base64_round_trips() {
+        for len in 0..8 {
+            let bytes = (0..len).map(|i| (i * 97 + 13) as u8).collect::<Vec<_>>();
+            let code = base64_encode(&bytes);
+            assert!(!code.contains('='), "{} is padded", code);
+            assert_eq!(base64_decode(&code).unwrap(), bytes);
+        }
+    }
+
+    #[test]
+    fn board_codes_round_trip() {
+        for (width, height) in [(3, 3), (4, 4), (5, 7), (8, 8)].iter() {
+            // Few enough spawns that even 3x3 keeps an empty cell to move into.
+            let board = played(*width, *height, 6).board;
+            let decoded = board_from_code(&board_code(&board).unwrap()).unwrap();
+            assert_eq!((decoded.width, decoded.height), (*width, *height));
+            assert_eq!(exps(&decoded), exps(&board));
+        }
+    }
+
+    #[test]
+    fn game_codes_round_trip() {
+        // 300 moves need a two-byte count.
+        for count in [0, 5, 300].iter() {
+            let game = played(4, 5, *count);
+            let record = record_from_code(&game_code(&game).unwrap()).unwrap();
+            assert_eq!(record.seed, game.seed);
+            assert_eq!(record.rules, game.rules);
+            assert_eq!(record.len(), game.moves.len());
+            assert_eq!(record.score, game.score);
+            assert_eq!(record.replay(record.len()).board, game.board);
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_board_codes() {
+        let code = board_code(&played(4, 4, 10).board).unwrap();
+        for bad in [
+            code[..code.len() - 2].to_string(),
+            format!("{}AA", code),
+            format!("{}A", code),
+            format!("{}=", &code[..code.len() - 1]),
+            code.replace(&code[1..2], "+"),
+            String::new(),
+        ]
+        .iter()
+        {
+            assert!(board_from_code(bad).is_err(), "{} decoded", bad);
+        }
+    }
+
+    #[test]
+    fn rejects_boards_without_moves() {
+        let empty = Board::new(4, 4);
+        let mut stuck = Board::new(3, 3);
+        for at in stuck.empty_cells() {
+            stuck.insert(at, 1 + (at.0 + at.1) as u8 % 2);
+        }
+        for board in [empty, stuck].iter() {
+            let code = board_code(board).unwrap();
+            assert!(board_from_code(&code).is_err(), "{} decoded", code);
+        }
+    }
+
+    #[test]
+    fn rejects_out_of_range_sizes() {
+        for size in [0x22, 0x29, 0x92, 0x99, 0x00].iter() {
+            let cells = (size >> 4) as usize * (size & 0xF) as usize;
+            let mut board = vec![*size];
+            board.resize(1 + cells.div_ceil(2), 0);
+            let code = base64_encode(&board);
+            assert!(board_from_code(&code).is_err(), "{} decoded", code);
+
+            let mut game = vec![*size];
+            game.extend_from_slice(&[0; 9]);
+            let code = base64_encode(&game);
+            assert!(record_from_code(&code).is_err(), "{} decoded", code);
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_game_codes() {
+        let bytes = base64_decode(&game_code(&played(4, 4, 20)).unwrap()).unwrap();
+        let truncated = bytes[..bytes.len() - 1].to_vec();
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        let mut overlong = bytes[..9].to_vec();
+        overlong.extend_from_slice(&[0xFF; 10]);
+
+        for bad in [truncated, trailing, overlong].iter() {
+            let code = base64_encode(bad);
+            assert!(record_from_code(&code).is_err(), "{} decoded", code);
+        }
+        assert!(record_from_code("not a code").is_err());
+    }
+
+    #[test]
+    fn rejects_moves_that_change_nothing() {
+        let (seed, index) = (0..)
+            .find_map(|seed| {
+                let board = Game::new(seed).board;
+                let index = MOVES.iter().position(|mv| !board.can_move(*mv))?;
+                Some((seed, index))
+            })
+            .unwrap();
+        let mut bytes = vec![size_byte(4, 4)];
+        bytes.extend_from_slice(&seed.to_le_bytes());
+        bytes.extend_from_slice(&[1, (index as u8) << 6]);
+        assert!(record_from_code(&base64_encode(&bytes)).is_err());
+    }
+}
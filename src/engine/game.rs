@@ -0,0 +1,141 @@
+use rand::SeedableRng;
+use rand_pcg::Pcg32;
+use serde::{Deserialize, Serialize};
+
+use super::board::{Board, Move, MoveOutcome};
+use super::rules::Rules;
+
+/// A board together with the RNG that feeds its spawns, so a game is fully
+/// determined by its `seed` and the `moves` played on it.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Game {
+    pub seed: u64,
+    pub rng: Pcg32,
+    pub rules: Rules,
+    pub board: Board,
+    pub score: u64,
+    /// Exponent of the highest tile reached so far.
+    pub highest: u8,
+    pub moves: Vec<Move>,
+    /// The board the game started from, when it wasn't dealt from the seed.
+    #[serde(default)]
+    pub start: Option<Board>,
+}
+
+impl Game {
+    pub fn new(seed: u64) -> Self {
+        Game::with_rules(seed, Rules::default())
+    }
+
+    pub fn with_rules(seed: u64, rules: Rules) -> Self {
+        let mut rng = Pcg32::seed_from_u64(seed);
+        let mut board = Board::new(rules.width, rules.height);
+        for _ in 0..rules.spawn.start_tiles {
+            board.add_random_tile(&mut rng, &rules.spawn);
+        }
+
+        Game {
+            seed,
+            rng,
+            rules,
+            highest: board.highest(),
+            board,
+            score: 0,
+            moves: Vec::new(),
+            start: None,
+        }
+    }
+
+    /// Starts from `board` instead of dealing the starting tiles; the seed
+    /// still drives every later spawn.
+    pub fn with_board(seed: u64, rules: Rules, board: Board) -> Result<Self, String> {
+        if (board.width, board.height) != (rules.width, rules.height) {
+            return Err(format!(
+                "a {}x{} board doesn't fit {}x{} rules",
+                board.width, board.height, rules.width, rules.height
+            ));
+        }
+        Ok(Game {
+            seed,
+            rng: Pcg32::seed_from_u64(seed),
+            rules,
+            highest: board.highest(),
+            start: Some(board.clone()),
+            board,
+            score: 0,
+            moves: Vec::new(),
+        })
+    }
+
+    /// Starts a game from a seed drawn from the thread RNG.
+    pub fn random() -> Self {
+        Game::new(rand::random())
+    }
+
+    /// Rebuilds the exact game that `moves` produced from `seed` under `rules`,
+    /// starting from `start` when it wasn't dealt from the seed.
+    pub fn replay(
+        seed: u64,
+        rules: Rules,
+        start: Option<Board>,
+        moves: &[Move],
+    ) -> Result<Self, String> {
+        let mut game = match start {
+            Some(board) => Game::with_board(seed, rules, board)?,
+            None => Game::with_rules(seed, rules),
+        };
+        for mv in moves {
+            game.play(*mv);
+        }
+        Ok(game)
+    }
+
+    /// Applies `mv` and, if anything changed, spawns the next tile.
+    pub fn play(&mut self, mv: Move) -> MoveOutcome {
+        let mut outcome = self.board.apply(mv);
+        if outcome.changed {
+            self.score += outcome.score;
+            self.highest = self.highest.max(outcome.highest);
+            self.moves.push(mv);
+            outcome.spawned = self.board.add_random_tile(&mut self.rng, &self.rules.spawn);
+        }
+        outcome
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.board.legal_moves().is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replay_reproduces_the_game() {
+        let mut game = Game::new(7);
+        let moves = [Move::Up, Move::Left, Move::Down, Move::Right];
+        for mv in moves.iter().cycle().take(100) {
+            game.play(*mv);
+        }
+        let replayed = Game::replay(game.seed, game.rules.clone(), None, &game.moves).unwrap();
+        assert_eq!(replayed.board, game.board);
+        assert_eq!(replayed.score, game.score);
+    }
+
+    #[test]
+    fn replay_starts_from_the_given_board() {
+        let mut start = Board::new(4, 4);
+        start.insert((0, 0), 3);
+        start.insert((0, 3), 3);
+        let mut game = Game::with_board(7, Rules::default(), start.clone()).unwrap();
+        game.play(Move::Left);
+        game.play(Move::Down);
+
+        let replayed =
+            Game::replay(game.seed, game.rules.clone(), Some(start), &game.moves).unwrap();
+        assert_eq!(replayed.board, game.board);
+        assert_eq!(replayed.score, 16);
+        assert!(Game::replay(7, Rules::default(), Some(Board::new(5, 5)), &[]).is_err());
+    }
+}
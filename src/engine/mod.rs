@@ -0,0 +1,20 @@
+//! The 2048 rules, free of any `mogwai`/`web_sys` dependency so they can be
+//! linked by native tools and tests as well as by the web front-end.
+pub mod ai;
+pub mod bitboard;
+pub mod board;
+pub mod code;
+pub mod game;
+pub mod position;
+pub mod record;
+pub mod rules;
+pub mod spawn;
+
+pub use ai::{best_move, solve, Budgeted, Expectimax, Heuristic, Solver};
+pub use bitboard::BitBoard;
+pub use board::{value_of, Board, Cell, Merge, Move, MoveOutcome, Slide, Spawn, Tile, MOVES};
+pub use game::Game;
+pub use position::Position;
+pub use record::{GameRecord, TimedMove, RECORD_VERSION};
+pub use rules::{Rules, WIN_EXP};
+pub use spawn::SpawnPolicy;
@@ -0,0 +1,78 @@
+use super::board::{Board, Cell, Move, MOVES};
+
+/// The operations searches and simulations need, shared by the tile-tracking
+/// `Board` and the packed `BitBoard`.
+pub trait Position: Clone {
+    /// Slides every tile towards `mv`, returning the points gained, or `None`
+    /// when nothing moved.
+    fn slide(&mut self, mv: Move) -> Option<u64>;
+
+    /// Exponent of the tile on `at`, 0 when the cell is empty.
+    fn exp(&self, at: Cell) -> u8;
+
+    /// Puts a `2^exp` tile on `at`, or clears it when `exp` is 0.
+    fn set(&mut self, at: Cell, exp: u8);
+
+    fn empty_cells(&self) -> Vec<Cell>;
+
+    fn highest(&self) -> u8;
+
+    fn width(&self) -> usize;
+
+    fn height(&self) -> usize;
+
+    fn can_move(&self, mv: Move) -> bool {
+        self.clone().slide(mv).is_some()
+    }
+
+    fn legal_moves(&self) -> Vec<Move> {
+        MOVES
+            .iter()
+            .copied()
+            .filter(|mv| self.can_move(*mv))
+            .collect()
+    }
+}
+
+impl Position for Board {
+    fn slide(&mut self, mv: Move) -> Option<u64> {
+        let outcome = self.apply(mv);
+        if outcome.changed {
+            Some(outcome.score)
+        } else {
+            None
+        }
+    }
+
+    fn exp(&self, at: Cell) -> u8 {
+        Board::exp(self, at)
+    }
+
+    fn set(&mut self, at: Cell, exp: u8) {
+        if exp == 0 {
+            self.remove(at);
+        } else {
+            self.insert(at, exp);
+        }
+    }
+
+    fn empty_cells(&self) -> Vec<Cell> {
+        Board::empty_cells(self)
+    }
+
+    fn highest(&self) -> u8 {
+        Board::highest(self)
+    }
+
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+
+    fn can_move(&self, mv: Move) -> bool {
+        Board::can_move(self, mv)
+    }
+}
@@ -0,0 +1,114 @@
+use serde::{Deserialize, Serialize};
+
+use super::board::{Board, Move};
+use super::game::Game;
+use super::rules::Rules;
+
+/// Bumped whenever the record layout changes; records from a newer version are refused.
+pub const RECORD_VERSION: u32 = 1;
+
+/// A move and when it was played, in milliseconds since the game started.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TimedMove {
+    pub mv: Move,
+    pub at: f64,
+}
+
+/// Everything needed to watch a game again: the spawns come back from the
+/// seed, the board from re-playing the moves through the engine.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GameRecord {
+    pub version: u32,
+    /// The kind of game, such as `"classic"`.
+    pub variant: String,
+    /// Board size and spawn policy.
+    pub rules: Rules,
+    pub seed: u64,
+    /// The board the game started from, when it wasn't dealt from the seed.
+    #[serde(default)]
+    pub start: Option<Board>,
+    pub moves: Vec<TimedMove>,
+    pub score: u64,
+    /// Exponent of the highest tile reached.
+    pub highest: u8,
+}
+
+impl GameRecord {
+    /// Records `game`; `times` holds when each of its moves was played.
+    pub fn new(game: &Game, variant: &str, times: &[f64]) -> Self {
+        GameRecord {
+            version: RECORD_VERSION,
+            variant: variant.to_string(),
+            rules: game.rules.clone(),
+            seed: game.seed,
+            start: game.start.clone(),
+            moves: game
+                .moves
+                .iter()
+                .enumerate()
+                .map(|(index, mv)| TimedMove {
+                    mv: *mv,
+                    at: times.get(index).copied().unwrap_or(0.0),
+                })
+                .collect(),
+            score: game.score,
+            highest: game.highest,
+        }
+    }
+
+    /// Parses a record and checks that re-playing it gives the score it claims.
+    pub fn from_json(json: &str) -> Result<GameRecord, String> {
+        let record: GameRecord =
+            serde_json::from_str(json).map_err(|err| format!("unreadable game record: {}", err))?;
+        if record.version > RECORD_VERSION {
+            return Err(format!(
+                "game record version {} is newer than this game ({})",
+                record.version, RECORD_VERSION
+            ));
+        }
+        record.verify()?;
+        Ok(record)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("game records always serialize")
+    }
+
+    pub fn len(&self) -> usize {
+        self.moves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.moves.is_empty()
+    }
+
+    /// The game after its first `count` moves.
+    pub fn replay(&self, count: usize) -> Game {
+        self.replayed(count)
+            .expect("A recorded start board always fits its rules")
+    }
+
+    fn replayed(&self, count: usize) -> Result<Game, String> {
+        let moves = self.moves.iter().take(count).map(|timed| timed.mv);
+        Game::replay(
+            self.seed,
+            self.rules.clone(),
+            self.start.clone(),
+            &moves.collect::<Vec<_>>(),
+        )
+    }
+
+    pub fn verify(&self) -> Result<(), String> {
+        let game = self.replayed(self.len())?;
+        if game.moves.len() != self.len() {
+            return Err("game record has moves that change nothing".to_string());
+        }
+        if game.score != self.score || game.highest != self.highest {
+            return Err(format!(
+                "game record claims {} points but re-plays to {}",
+                self.score, game.score
+            ));
+        }
+        Ok(())
+    }
+}
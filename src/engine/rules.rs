@@ -0,0 +1,83 @@
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+use super::spawn::SpawnPolicy;
+
+pub const MIN_SIZE: usize = 3;
+pub const MAX_SIZE: usize = 8;
+
+/// Exponent of the 2048 tile.
+pub const WIN_EXP: u8 = 11;
+
+/// Everything that distinguishes one game variant from another.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Rules {
+    pub width: usize,
+    pub height: usize,
+    pub spawn: SpawnPolicy,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Rules {
+            width: 4,
+            height: 4,
+            spawn: SpawnPolicy::default(),
+        }
+    }
+}
+
+impl Rules {
+    pub fn new(width: usize, height: usize) -> Result<Rules, String> {
+        for side in [width, height].iter() {
+            if *side < MIN_SIZE || *side > MAX_SIZE {
+                return Err(format!(
+                    "board sides must be between {} and {}, got {}x{}",
+                    MIN_SIZE, MAX_SIZE, width, height
+                ));
+            }
+        }
+
+        Ok(Rules {
+            width,
+            height,
+            ..Rules::default()
+        })
+    }
+
+    pub fn square(size: usize) -> Result<Rules, String> {
+        Rules::new(size, size)
+    }
+
+    pub fn with_spawn(self, spawn: SpawnPolicy) -> Rules {
+        Rules { spawn, ..self }
+    }
+}
+
+impl FromStr for Rules {
+    type Err = String;
+
+    /// Classic rules on a board given as `5` or `4x6` (width x height).
+    fn from_str(s: &str) -> Result<Rules, String> {
+        let (width, height) = s.split_once('x').unwrap_or((s, s));
+        let side = |side: &str| {
+            side.parse::<usize>()
+                .map_err(|_| format!("invalid board size: {:?}", s))
+        };
+        Rules::new(side(width)?, side(height)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_sizes() {
+        assert_eq!("5".parse::<Rules>(), Rules::new(5, 5));
+        assert_eq!("4x6".parse::<Rules>(), Rules::new(4, 6));
+        for bad in ["", "x", "4x", "4x6x8", "-4", "2", "9x4"].iter() {
+            assert!(bad.parse::<Rules>().is_err(), "{:?} parsed", bad);
+        }
+    }
+}
@@ -0,0 +1,81 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+/// Which tiles appear after a move and how many the board starts with.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SpawnPolicy {
+    /// `(exp, weight)` pairs; a spawned `2^exp` tile is drawn proportionally to the weights.
+    pub weights: Vec<(u8, u32)>,
+    pub start_tiles: usize,
+}
+
+impl Default for SpawnPolicy {
+    /// The classic rule: a 2 nine times out of ten, otherwise a 4, and two starting tiles.
+    fn default() -> Self {
+        SpawnPolicy {
+            weights: vec![(1, 9), (2, 1)],
+            start_tiles: 2,
+        }
+    }
+}
+
+impl SpawnPolicy {
+    pub fn new(weights: Vec<(u8, u32)>, start_tiles: usize) -> Self {
+        SpawnPolicy {
+            weights,
+            start_tiles,
+        }
+    }
+
+    /// Each exponent that can spawn with its probability. Without any weight
+    /// a 2 always spawns.
+    pub fn probabilities(&self) -> Vec<(u8, f64)> {
+        let total: u32 = self.weights.iter().map(|(_, weight)| weight).sum();
+        if total == 0 {
+            return vec![(1, 1.0)];
+        }
+        self.weights
+            .iter()
+            .filter(|(_, weight)| *weight > 0)
+            .map(|(exp, weight)| (*exp, *weight as f64 / total as f64))
+            .collect()
+    }
+
+    /// Draws the exponent of the next tile.
+    pub fn pick<R: Rng + ?Sized>(&self, rng: &mut R) -> u8 {
+        let total: u32 = self.weights.iter().map(|(_, weight)| weight).sum();
+        if total == 0 {
+            return 1;
+        }
+
+        let mut roll = rng.gen_range(0..total);
+        for (exp, weight) in self.weights.iter() {
+            if roll < *weight {
+                return *exp;
+            }
+            roll -= weight;
+        }
+        unreachable!("roll is always below the total weight")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_pcg::Pcg32;
+
+    #[test]
+    fn probabilities_follow_the_weights() {
+        let policy = SpawnPolicy::new(vec![(1, 3), (2, 1), (3, 0)], 2);
+        assert_eq!(policy.probabilities(), vec![(1, 0.75), (2, 0.25)]);
+    }
+
+    #[test]
+    fn no_weight_spawns_a_two() {
+        let policy = SpawnPolicy::new(vec![(2, 0)], 2);
+        let mut rng = Pcg32::seed_from_u64(0);
+        assert_eq!(policy.pick(&mut rng), 1);
+        assert_eq!(policy.probabilities(), vec![(1, 1.0)]);
+    }
+}
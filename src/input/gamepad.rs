@@ -0,0 +1,107 @@
+use super::Command;
+use crate::engine::Move;
+
+/// Buttons of the W3C "standard" gamepad layout that the game listens to.
+const DPAD: [(usize, Move); 4] = [
+    (12, Move::Up),
+    (13, Move::Down),
+    (14, Move::Left),
+    (15, Move::Right),
+];
+const BUTTONS: [(usize, Command); 4] = [
+    (1, Command::Undo),
+    (2, Command::Hint),
+    (3, Command::Redo),
+    (9, Command::Restart),
+];
+
+/// What a controller reported on one animation frame.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PadState {
+    /// Stick axes in `-1.0..=1.0`, the left stick first with `y` pointing down.
+    pub axes: Vec<f64>,
+    pub buttons: Vec<bool>,
+}
+
+impl PadState {
+    fn pressed(&self, button: usize) -> bool {
+        self.buttons.get(button).copied().unwrap_or(false)
+    }
+}
+
+/// Turns successive `PadState`s into commands: a held direction repeats
+/// after a delay, buttons fire once per press.
+#[derive(Clone, Debug)]
+pub struct PadInput {
+    /// Stick deflection below which the stick counts as centered.
+    pub dead_zone: f64,
+    /// Milliseconds a direction is held before it starts repeating.
+    pub repeat_delay: f64,
+    /// Milliseconds between repeats after that.
+    pub repeat_interval: f64,
+    /// The direction being held and when it fires next.
+    held: Option<(Move, f64)>,
+    previous: Vec<bool>,
+}
+
+impl Default for PadInput {
+    fn default() -> Self {
+        PadInput {
+            dead_zone: 0.5,
+            repeat_delay: 400.0,
+            repeat_interval: 200.0,
+            held: None,
+            previous: Vec::new(),
+        }
+    }
+}
+
+impl PadInput {
+    /// The direction pushed on the D-pad, or else on the left stick.
+    pub fn direction(&self, state: &PadState) -> Option<Move> {
+        if let Some((_, mv)) = DPAD.iter().find(|(button, _)| state.pressed(*button)) {
+            return Some(*mv);
+        }
+
+        let x = state.axes.first().copied().unwrap_or(0.0);
+        let y = state.axes.get(1).copied().unwrap_or(0.0);
+        if x.abs().max(y.abs()) < self.dead_zone {
+            None
+        } else if x.abs() >= y.abs() {
+            Some(if x > 0.0 { Move::Right } else { Move::Left })
+        } else {
+            Some(if y > 0.0 { Move::Down } else { Move::Up })
+        }
+    }
+
+    /// The commands triggered by `state`, read at `now` milliseconds.
+    pub fn poll(&mut self, state: &PadState, now: f64) -> Vec<Command> {
+        let mut commands = Vec::new();
+
+        self.held = match (self.direction(state), self.held) {
+            (Some(mv), Some((held, next))) if mv == held => {
+                if now >= next {
+                    commands.push(Command::Move(mv));
+                    Some((mv, now + self.repeat_interval))
+                } else {
+                    Some((mv, next))
+                }
+            }
+            (Some(mv), _) => {
+                commands.push(Command::Move(mv));
+                Some((mv, now + self.repeat_delay))
+            }
+            (None, _) => None,
+        };
+
+        for (button, command) in BUTTONS.iter() {
+            let was_pressed = self.previous.get(*button).copied().unwrap_or(false);
+            if state.pressed(*button) && !was_pressed {
+                commands.push(*command);
+            }
+        }
+        self.previous = state.buttons.clone();
+
+        commands
+    }
+}
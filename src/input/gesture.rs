@@ -0,0 +1,112 @@
+use crate::engine::Move;
+
+/// Where and when a pointer went down or up: CSS pixels, with `y` growing
+/// downwards, and milliseconds.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+    pub t: f64,
+}
+
+/// Thresholds a pointer gesture has to meet to count as a swipe.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Swipe {
+    /// Shortest travel along the dominant axis, in pixels.
+    pub min_distance: f64,
+    /// Slowest accepted speed along the dominant axis, in pixels per millisecond.
+    pub min_velocity: f64,
+    /// How many times longer the dominant axis must be than the other one;
+    /// anything closer to a diagonal is ignored.
+    pub axis_ratio: f64,
+}
+
+impl Default for Swipe {
+    fn default() -> Self {
+        Swipe {
+            min_distance: 30.0,
+            min_velocity: 0.2,
+            axis_ratio: 1.5,
+        }
+    }
+}
+
+impl Swipe {
+    /// The move a gesture from `start` to `end` stands for, if it is a swipe at all.
+    pub fn classify(&self, start: Point, end: Point) -> Option<Move> {
+        let (dx, dy) = (end.x - start.x, end.y - start.y);
+        let (along, across) = if dx.abs() >= dy.abs() {
+            (dx.abs(), dy.abs())
+        } else {
+            (dy.abs(), dx.abs())
+        };
+
+        if along < self.min_distance || along < self.axis_ratio * across {
+            return None;
+        }
+        // A zero duration happens with coarse timers; treat it as instant.
+        let elapsed = (end.t - start.t).max(1.0);
+        if along / elapsed < self.min_velocity {
+            return None;
+        }
+
+        Some(if dx.abs() >= dy.abs() {
+            if dx > 0.0 {
+                Move::Right
+            } else {
+                Move::Left
+            }
+        } else if dy > 0.0 {
+            Move::Down
+        } else {
+            Move::Up
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn swipe(dx: f64, dy: f64, millis: f64) -> Option<Move> {
+        let start = Point {
+            x: 100.0,
+            y: 100.0,
+            t: 1000.0,
+        };
+        let end = Point {
+            x: start.x + dx,
+            y: start.y + dy,
+            t: start.t + millis,
+        };
+        Swipe::default().classify(start, end)
+    }
+
+    #[test]
+    fn each_direction() {
+        assert_eq!(swipe(0.0, -80.0, 100.0), Some(Move::Up));
+        assert_eq!(swipe(0.0, 80.0, 100.0), Some(Move::Down));
+        assert_eq!(swipe(-80.0, 10.0, 100.0), Some(Move::Left));
+        assert_eq!(swipe(80.0, -10.0, 100.0), Some(Move::Right));
+    }
+
+    #[test]
+    fn too_short() {
+        assert_eq!(swipe(20.0, 0.0, 50.0), None);
+    }
+
+    #[test]
+    fn too_slow() {
+        assert_eq!(swipe(80.0, 0.0, 1000.0), None);
+    }
+
+    #[test]
+    fn near_diagonal() {
+        assert_eq!(swipe(80.0, 70.0, 100.0), None);
+    }
+
+    #[test]
+    fn zero_duration_counts_as_instant() {
+        assert_eq!(swipe(0.0, 80.0, 0.0), Some(Move::Down));
+    }
+}
@@ -0,0 +1,115 @@
+use serde::{Deserialize, Serialize};
+
+use super::Command;
+use crate::engine::MOVES;
+use crate::storage;
+
+/// localStorage key of the player's key bindings.
+const KEY_BINDINGS: &str = "2048.keys";
+
+/// Ready-made layouts; each one keeps the arrow keys and adds its own moves.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Preset {
+    Arrows,
+    Wasd,
+    Vim,
+    Numpad,
+}
+
+pub const PRESETS: [Preset; 4] = [Preset::Arrows, Preset::Wasd, Preset::Vim, Preset::Numpad];
+
+impl Preset {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Preset::Arrows => "Arrows",
+            Preset::Wasd => "WASD",
+            Preset::Vim => "hjkl",
+            Preset::Numpad => "Numpad",
+        }
+    }
+
+    /// Keys for up, left, down and right (the order of `MOVES`), on top of the arrows.
+    fn moves(&self) -> Option<[&'static str; 4]> {
+        match self {
+            Preset::Arrows => None,
+            Preset::Wasd => Some(["w", "a", "s", "d"]),
+            Preset::Vim => Some(["k", "h", "j", "l"]),
+            Preset::Numpad => Some(["8", "4", "2", "6"]),
+        }
+    }
+}
+
+/// Which `KeyboardEvent::key()` triggers which command. A key maps to at
+/// most one command, a command may have several keys.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct KeyBindings {
+    pub keys: Vec<(String, Command)>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        KeyBindings::preset(Preset::Arrows)
+    }
+}
+
+impl KeyBindings {
+    pub fn preset(preset: Preset) -> Self {
+        let mut bindings = KeyBindings { keys: Vec::new() };
+        let arrows = ["ArrowUp", "ArrowLeft", "ArrowDown", "ArrowRight"];
+        for keys in [Some(arrows), preset.moves()].iter().flatten() {
+            for (key, mv) in keys.iter().zip(MOVES.iter()) {
+                bindings.bind(key, Command::Move(*mv));
+            }
+        }
+        bindings.bind("z", Command::Undo);
+        bindings.bind("y", Command::Redo);
+        bindings.bind("n", Command::Restart);
+        bindings.bind("?", Command::Hint);
+        bindings
+    }
+
+    /// The stored bindings, or the arrow preset if none were saved.
+    pub fn load() -> Self {
+        storage::load(KEY_BINDINGS).unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        storage::save(KEY_BINDINGS, self);
+    }
+
+    pub fn command(&self, key: &str) -> Option<Command> {
+        let key = normalize(key);
+        self.keys
+            .iter()
+            .find(|(bound, _)| *bound == key)
+            .map(|(_, command)| *command)
+    }
+
+    pub fn keys_for(&self, command: Command) -> Vec<&str> {
+        self.keys
+            .iter()
+            .filter(|(_, bound)| *bound == command)
+            .map(|(key, _)| key.as_str())
+            .collect()
+    }
+
+    /// Makes `key` trigger `command`, taking it away from any other command.
+    pub fn bind(&mut self, key: &str, command: Command) {
+        let key = normalize(key);
+        self.keys.retain(|(bound, _)| *bound != key);
+        self.keys.push((key, command));
+    }
+
+    pub fn clear(&mut self, command: Command) {
+        self.keys.retain(|(_, bound)| *bound != command);
+    }
+}
+
+/// Letters are matched regardless of case, so caps lock doesn't get in the way.
+fn normalize(key: &str) -> String {
+    if key.chars().count() == 1 {
+        key.to_lowercase()
+    } else {
+        key.to_string()
+    }
+}
@@ -0,0 +1,49 @@
+//! Turns raw player input into game commands. The gesture and key
+//! classifiers take plain numbers and strings rather than DOM events.
+use serde::{Deserialize, Serialize};
+
+use crate::engine::Move;
+
+pub mod gamepad;
+pub mod gesture;
+pub mod keys;
+
+pub use gamepad::{PadInput, PadState};
+pub use gesture::{Point, Swipe};
+pub use keys::{KeyBindings, Preset, PRESETS};
+
+/// Something the player can ask of the `Play` screen.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Command {
+    Move(Move),
+    Undo,
+    Redo,
+    Restart,
+    Hint,
+}
+
+pub const COMMANDS: [Command; 8] = [
+    Command::Move(Move::Up),
+    Command::Move(Move::Left),
+    Command::Move(Move::Down),
+    Command::Move(Move::Right),
+    Command::Undo,
+    Command::Redo,
+    Command::Restart,
+    Command::Hint,
+];
+
+impl Command {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Command::Move(Move::Up) => "Move up",
+            Command::Move(Move::Left) => "Move left",
+            Command::Move(Move::Down) => "Move down",
+            Command::Move(Move::Right) => "Move right",
+            Command::Undo => "Undo",
+            Command::Redo => "Redo",
+            Command::Restart => "Restart",
+            Command::Hint => "Hint",
+        }
+    }
+}
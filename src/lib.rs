@@ -8,7 +8,13 @@ use web_sys::HashChangeEvent;
 
 mod components;
 mod containers;
+mod daily;
+pub mod engine;
+mod input;
+mod records;
 mod router;
+mod stats;
+mod storage;
 
 use crate::router::Route;
 use components::grid::*;
@@ -136,7 +142,16 @@ impl Component for App {
                             <a href=String::from(Route::Home)>"Home"</a>
                         </li>
                         <li class=self.route.nav_play_class()>
-                            <a href=String::from(Route::Play)>"Play"</a>
+                            <a href=String::from(Route::play())>"Play"</a>
+                        </li>
+                        <li class=self.route.nav_daily_class()>
+                            <a href=String::from(Route::Daily)>"Daily"</a>
+                        </li>
+                        <li class=self.route.nav_stats_class()>
+                            <a href=String::from(Route::Stats)>"Stats"</a>
+                        </li>
+                        <li class=self.route.nav_settings_class()>
+                            <a href=String::from(Route::Settings)>"Controls"</a>
                         </li>
                     </ul>
                 </nav>
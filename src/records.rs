@@ -0,0 +1,80 @@
+use log::warn;
+use mogwai::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::engine::GameRecord;
+use crate::storage;
+
+/// localStorage key of the recorded games.
+const RECORDS: &str = "2048.records";
+
+/// How many finished games are kept, the oldest being dropped first.
+const KEEP: usize = 20;
+
+#[derive(Clone, Debug)]
+pub struct Stored {
+    pub id: String,
+    pub record: GameRecord,
+}
+
+/// A record as kept in localStorage, in the JSON of `GameRecord::to_json`.
+#[derive(Serialize, Deserialize)]
+struct Kept {
+    id: String,
+    json: String,
+}
+
+/// Every kept record, oldest first. Records `GameRecord::from_json` refuses,
+/// like ones from a newer version, are left out.
+pub fn list() -> Vec<Stored> {
+    let kept: Vec<Kept> = storage::load(RECORDS).unwrap_or_default();
+    kept.into_iter()
+        .filter_map(|kept| match GameRecord::from_json(&kept.json) {
+            Ok(record) => Some(Stored {
+                id: kept.id,
+                record,
+            }),
+            Err(err) => {
+                warn!("ignoring game record {}: {}", kept.id, err);
+                None
+            }
+        })
+        .collect()
+}
+
+pub fn load(id: &str) -> Option<GameRecord> {
+    list()
+        .into_iter()
+        .find(|stored| stored.id == id)
+        .map(|stored| stored.record)
+}
+
+pub fn latest() -> Option<GameRecord> {
+    list().pop().map(|stored| stored.record)
+}
+
+/// Keeps `record` and returns the id to replay it with.
+pub fn save(record: &GameRecord) -> String {
+    // Replaying a seed can give the same game length again, so the save time
+    // keeps the ids apart.
+    let performance = window().performance().expect("Performance not supported");
+    let saved = (performance.time_origin() + performance.now()) as u64;
+    let id = format!("{:x}-{}-{:x}", record.seed, record.len(), saved);
+    let mut records = list();
+    records.push(Stored {
+        id: id.clone(),
+        record: record.clone(),
+    });
+    if records.len() > KEEP {
+        records.drain(..records.len() - KEEP);
+    }
+    let kept: Vec<Kept> = records
+        .iter()
+        .map(|stored| Kept {
+            id: stored.id.clone(),
+            json: stored.record.to_json(),
+        })
+        .collect();
+    storage::save(RECORDS, &kept);
+    id
+}
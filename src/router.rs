@@ -1,16 +1,201 @@
 use log::info;
 use mogwai::prelude::*;
+use std::fmt;
+use std::str::FromStr;
 
+use crate::containers::daily::DailyPage;
 use crate::containers::home::Home;
 use crate::containers::play::Play;
+use crate::containers::replay::Replay;
+use crate::containers::settings::Settings;
+use crate::containers::stats::StatsPage;
+use crate::containers::win::Win;
+use crate::engine::code::{board_code, board_from_code, game_code, record_from_code};
+use crate::engine::{Board, Game, GameRecord, Rules};
+use crate::daily::{self, Daily};
+use crate::records;
+
+/// A `#/path/segments?key=value&...` location, split but not yet interpreted.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HashRoute {
+    pub segments: Vec<String>,
+    pub query: Vec<(String, String)>,
+}
+
+impl HashRoute {
+    pub fn new(segments: &[&str]) -> Self {
+        HashRoute {
+            segments: segments.iter().map(|segment| segment.to_string()).collect(),
+            query: Vec::new(),
+        }
+    }
+
+    pub fn with_param<T: ToString>(mut self, key: &str, value: T) -> Self {
+        self.query.push((key.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn parse(s: &str) -> Result<HashRoute, String> {
+        // remove the scheme, if it has one
+        let hash_split = s.split('#').collect::<Vec<_>>();
+        let after_hash = match hash_split.as_slice() {
+            [_, after] => Ok(*after),
+            _ => Err(format!("route must have a hash: {}", s)),
+        }?;
+
+        let (path, query) = match after_hash.split_once('?') {
+            Some((path, query)) => (path, Some(query)),
+            None => (after_hash, None),
+        };
+
+        if !path.is_empty() && !path.starts_with('/') {
+            return Err(format!("route path must start with '/': {}", s));
+        }
+        let path = path.strip_prefix('/').unwrap_or(path);
+        let trimmed = path.strip_suffix('/').unwrap_or(path);
+        let segments = if trimmed.is_empty() {
+            Vec::new()
+        } else {
+            trimmed.split('/').map(String::from).collect::<Vec<_>>()
+        };
+        if segments.iter().any(String::is_empty) {
+            return Err(format!("route has an empty path segment: {}", s));
+        }
+
+        let mut route = HashRoute {
+            segments,
+            query: Vec::new(),
+        };
+        for pair in query.into_iter().flat_map(|query| query.split('&')) {
+            let (key, value) = pair
+                .split_once('=')
+                .ok_or_else(|| format!("query parameter without a value: {:?}", pair))?;
+            if key.is_empty() {
+                return Err(format!("query parameter without a name: {:?}", pair));
+            }
+            if route.query.iter().any(|(k, _)| k == key) {
+                return Err(format!("query parameter given twice: {:?}", key));
+            }
+            route.query.push((key.to_string(), value.to_string()));
+        }
+        Ok(route)
+    }
+
+    pub fn segments(&self) -> Vec<&str> {
+        self.segments.iter().map(String::as_str).collect()
+    }
+
+    /// The value of `key` parsed as `T`, `None` when absent.
+    pub fn param<T>(&self, key: &str) -> Result<Option<T>, String>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        self.decode(key, |value| value.parse().map_err(|err: T::Err| err.to_string()))
+    }
+
+    /// The value of `key` read with `decode`, `None` when absent.
+    pub fn decode<T>(
+        &self,
+        key: &str,
+        decode: impl FnOnce(&str) -> Result<T, String>,
+    ) -> Result<Option<T>, String> {
+        match self.query.iter().find(|(k, _)| k == key) {
+            Some((_, value)) => decode(value)
+                .map(Some)
+                .map_err(|err| format!("invalid value for {}: {:?} ({})", key, value, err)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn require<T>(&self, key: &str) -> Result<T, String>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        self.param(key)?
+            .ok_or_else(|| format!("missing query parameter: {}", key))
+    }
+}
+
+impl fmt::Display for HashRoute {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#/{}", self.segments.join("/"))?;
+        for (i, (key, value)) in self.query.iter().enumerate() {
+            write!(f, "{}{}={}", if i == 0 { "?" } else { "&" }, key, value)?;
+        }
+        Ok(())
+    }
+}
+
+/// A board size given as `5` or `4x6` (width x height).
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct BoardSize {
+    pub width: usize,
+    pub height: usize,
+}
+
+impl FromStr for BoardSize {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<BoardSize, String> {
+        let rules: Rules = s.parse()?;
+        Ok(BoardSize {
+            width: rules.width,
+            height: rules.height,
+        })
+    }
+}
+
+impl fmt::Display for BoardSize {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.width == self.height {
+            write!(f, "{}", self.width)
+        } else {
+            write!(f, "{}x{}", self.width, self.height)
+        }
+    }
+}
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum Route {
     Home,
-    Play,
+    Play {
+        size: Option<BoardSize>,
+        seed: Option<u64>,
+        /// A shared position to start from instead of a fresh deal.
+        board: Option<Board>,
+    },
+    Win {
+        score: u64,
+        tile: u64,
+        moves: usize,
+        secs: u64,
+    },
+    /// A recorded game, the latest one without an id.
+    Replay {
+        id: Option<String>,
+    },
+    /// A game shared as a code.
+    SharedGame {
+        record: GameRecord,
+    },
+    /// Today's challenge, or its results once played.
+    Daily,
+    Stats,
+    Settings,
 }
 
 impl Route {
+    /// Plain `#/play`: resume the current game or start a classic one.
+    pub fn play() -> Route {
+        Route::Play {
+            size: None,
+            seed: None,
+            board: None,
+        }
+    }
+
     pub fn nav_home_class(&self) -> String {
         match self {
             Route::Home => "nav-link active",
@@ -26,40 +211,128 @@ impl Route {
         }
         .to_string()
     }
+
+    pub fn nav_daily_class(&self) -> String {
+        match self {
+            Route::Daily => "nav-link active",
+            _ => "nav-link",
+        }
+        .to_string()
+    }
+
+    pub fn nav_stats_class(&self) -> String {
+        match self {
+            Route::Stats => "nav-link active",
+            _ => "nav-link",
+        }
+        .to_string()
+    }
+
+    pub fn nav_settings_class(&self) -> String {
+        match self {
+            Route::Settings => "nav-link active",
+            _ => "nav-link",
+        }
+        .to_string()
+    }
 }
 
 impl TryFrom<&str> for Route {
     type Error = String;
 
     fn try_from(s: &str) -> Result<Route, String> {
-        // remove the scheme, if it has one
-        let hash_split = s.split("#").collect::<Vec<_>>();
-        let after_hash = match hash_split.as_slice() {
-            [_, after] => Ok(after),
-            _ => Err(format!("route must have a hash: {}", s)),
-        }?;
+        let route = HashRoute::parse(s)?;
 
-        let paths: Vec<&str> = after_hash.split("/").collect::<Vec<_>>();
-
-        info!("pathss: {:?}, as_slice:{:?}", paths, paths.as_slice());
-        match paths.as_slice() {
-            [""] => Ok(Route::Home),
-            ["", ""] => Ok(Route::Home),
-            ["", "play"] => Ok(Route::Play),
+        info!("route: {:?}", route);
+        match route.segments().as_slice() {
+            [] => Ok(Route::Home),
+            ["play"] => {
+                let size: Option<BoardSize> = route.param("size")?;
+                let board = route.decode("board", board_from_code)?;
+                if let (Some(size), Some(board)) = (size, &board) {
+                    if (size.width, size.height) != (board.width, board.height) {
+                        return Err(format!(
+                            "size {} doesn't match the {}x{} board",
+                            size, board.width, board.height
+                        ));
+                    }
+                }
+                Ok(Route::Play {
+                    size,
+                    seed: route.param("seed")?,
+                    board,
+                })
+            }
+            ["win"] => Ok(Route::Win {
+                score: route.require("sc")?,
+                tile: route.param("tile")?.unwrap_or(0),
+                moves: route.param("mv")?.unwrap_or(0),
+                secs: route.param("t")?.unwrap_or(0),
+            }),
+            ["replay"] => match route.decode("g", record_from_code)? {
+                Some(record) => Ok(Route::SharedGame { record }),
+                None => Ok(Route::Replay { id: None }),
+            },
+            ["replay", id] => Ok(Route::Replay {
+                id: Some(id.to_string()),
+            }),
+            ["daily"] => Ok(Route::Daily),
+            ["stats"] => Ok(Route::Stats),
+            ["settings"] => Ok(Route::Settings),
             r => Err(format!("unsupported route: {:?}", r)),
         }
     }
 }
 
-impl From<Route> for String {
-    fn from(route: Route) -> String {
+impl From<&Route> for HashRoute {
+    fn from(route: &Route) -> HashRoute {
         match route {
-            Route::Home => "#/".into(),
-            Route::Play => "#/play".into(),
+            Route::Home => HashRoute::new(&[]),
+            Route::Play { size, seed, board } => {
+                let mut hash = HashRoute::new(&["play"]);
+                if let Some(size) = size {
+                    hash = hash.with_param("size", size);
+                }
+                if let Some(seed) = seed {
+                    hash = hash.with_param("seed", seed);
+                }
+                if let Some(code) = board.as_ref().and_then(|board| board_code(board).ok()) {
+                    hash = hash.with_param("board", code);
+                }
+                hash
+            }
+            Route::Win {
+                score,
+                tile,
+                moves,
+                secs,
+            } => HashRoute::new(&["win"])
+                .with_param("sc", score)
+                .with_param("tile", tile)
+                .with_param("mv", moves)
+                .with_param("t", secs),
+            Route::Replay { id: None } => HashRoute::new(&["replay"]),
+            Route::Replay { id: Some(id) } => HashRoute::new(&["replay", id]),
+            Route::SharedGame { record } => {
+                let hash = HashRoute::new(&["replay"]);
+                match game_code(&record.replay(record.len())) {
+                    Ok(code) => hash.with_param("g", code),
+                    Err(_) => hash,
+                }
+            }
+            Route::Daily => HashRoute::new(&["daily"]),
+            Route::Stats => HashRoute::new(&["stats"]),
+            Route::Settings => HashRoute::new(&["settings"]),
         }
     }
 }
 
+impl From<Route> for String {
+    fn from(route: Route) -> String {
+        HashRoute::from(&route).to_string()
+    }
+}
+
 impl From<&Route> for ViewBuilder<HtmlElement> {
     fn from(route: &Route) -> Self {
         match route {
@@ -72,14 +345,133 @@ impl From<&Route> for ViewBuilder<HtmlElement> {
                     </main>
                 }
             }
-            Route::Play => {
-                let play_component = Gizmo::from(Play::default());
+            Route::Play {
+                size: None,
+                seed: None,
+                board: None,
+            } => {
+                // Like Home, a saved game that's already over doesn't count as one to resume.
+                let play = Play::resume().filter(|play| !play.game_over).unwrap_or_default();
+                let play_component = Gizmo::from(play);
+                builder! {
+                    <main class="content">
+                        {play_component.view_builder()}
+                    </main>
+                }
+            }
+            Route::Play { size, seed, board } => {
+                let rules = match (size, board) {
+                    (_, Some(board)) => Rules::new(board.width, board.height)
+                        .expect("Board was validated by the router"),
+                    (Some(size), None) => Rules::new(size.width, size.height)
+                        .expect("Board size was validated by the router"),
+                    (None, None) => Rules::default(),
+                };
+                let seed = seed.unwrap_or_else(rand::random);
+                let game = match board {
+                    Some(board) => Game::with_board(seed, rules, board.clone())
+                        .expect("Board was validated by the router"),
+                    None => Game::with_rules(seed, rules),
+                };
+                // A new game takes over the save slot, so the one in it is counted first.
+                Play::discard_saved();
+                let play_component = Gizmo::from(Play::new(game));
                 builder! {
                     <main class="content">
                         {play_component.view_builder()}
                     </main>
                 }
             }
+            Route::Win {
+                score,
+                tile,
+                moves,
+                secs,
+            } => {
+                let win_component = Gizmo::from(Win {
+                    score: *score,
+                    tile: *tile,
+                    moves: *moves,
+                    secs: *secs,
+                });
+                builder! {
+                    <main class="content">
+                        {win_component.view_builder()}
+                    </main>
+                }
+            }
+            Route::Replay { id } => {
+                let record = match id {
+                    Some(id) => records::load(id),
+                    None => records::latest(),
+                };
+                match record {
+                    Some(record) => {
+                        let replay_component = Gizmo::from(Replay::new(record));
+                        builder! {
+                            <main class="content">
+                                {replay_component.view_builder()}
+                            </main>
+                        }
+                    }
+                    None => builder! {
+                        <main class="content">
+                            <pre class="pre-error">{format!("No recorded game {}", id.as_deref().unwrap_or("yet"))}</pre>
+                        </main>
+                    },
+                }
+            }
+            Route::SharedGame { record } => {
+                let replay_component = Gizmo::from(Replay::new(record.clone()));
+                builder! {
+                    <main class="content">
+                        {replay_component.view_builder()}
+                    </main>
+                }
+            }
+            Route::Daily => {
+                let today = daily::today();
+                let daily = Daily::load();
+                match daily.result(today).cloned() {
+                    Some(result) => {
+                        let daily_component = Gizmo::from(DailyPage {
+                            day: today,
+                            result,
+                            daily,
+                        });
+                        builder! {
+                            <main class="content">
+                                {daily_component.view_builder()}
+                            </main>
+                        }
+                    }
+                    None => {
+                        let play = Play::resume_daily(today).unwrap_or_else(|| Play::daily(today));
+                        let play_component = Gizmo::from(play);
+                        builder! {
+                            <main class="content">
+                                {play_component.view_builder()}
+                            </main>
+                        }
+                    }
+                }
+            }
+            Route::Stats => {
+                let stats_component = Gizmo::from(StatsPage::default());
+                builder! {
+                    <main class="content">
+                        {stats_component.view_builder()}
+                    </main>
+                }
+            }
+            Route::Settings => {
+                let settings_component = Gizmo::from(Settings::default());
+                builder! {
+                    <main class="content">
+                        {settings_component.view_builder()}
+                    </main>
+                }
+            }
         }
     }
 }
@@ -89,3 +481,60 @@ impl From<&Route> for View<HtmlElement> {
         ViewBuilder::from(route).into()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_malformed_hashes() {
+        for bad in [
+            "http://localhost/play",
+            "#play",
+            "#/play//x",
+            "#/play?size",
+            "#/play?=5",
+            "#/play?seed=1&seed=2",
+        ]
+        .iter()
+        {
+            assert!(HashRoute::parse(bad).is_err(), "{} parsed", bad);
+        }
+    }
+
+    #[test]
+    fn rejects_bad_parameters() {
+        for bad in [
+            "#/play?size=2",
+            "#/play?size=4x",
+            "#/play?size=big",
+            "#/play?seed=-1",
+            "#/play?seed=abc",
+            "#/win",
+            "#/nowhere",
+        ]
+        .iter()
+        {
+            assert!(Route::try_from(*bad).is_err(), "{} parsed", bad);
+        }
+    }
+
+    #[test]
+    fn routes_round_trip() {
+        for hash in ["#/play?size=5&seed=123", "#/play?size=4x6", "#/replay/1f-42"].iter() {
+            let route = Route::try_from(*hash).unwrap();
+            assert_eq!(String::from(route), *hash);
+        }
+        assert_eq!(
+            Route::try_from("http://localhost/#/play?size=5&seed=123").unwrap(),
+            Route::Play {
+                size: Some(BoardSize {
+                    width: 5,
+                    height: 5
+                }),
+                seed: Some(123),
+                board: None,
+            }
+        );
+    }
+}
@@ -0,0 +1,55 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::engine::Game;
+use crate::storage;
+
+/// localStorage key of the lifetime statistics.
+const STATS: &str = "2048.stats";
+
+/// Lifetime statistics over every finished game, kept in localStorage.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Stats {
+    pub best_score: u64,
+    /// Exponent of the highest tile ever reached.
+    pub best_tile: u8,
+    pub games_played: u32,
+    pub games_won: u32,
+    pub total_score: u64,
+    pub total_moves: u64,
+    #[serde(default)]
+    pub hints_used: u64,
+    /// Number of games per highest tile exponent reached.
+    pub max_tiles: BTreeMap<u8, u32>,
+}
+
+impl Stats {
+    pub fn load() -> Stats {
+        storage::load(STATS).unwrap_or_default()
+    }
+
+    /// Adds a finished or abandoned game to the stored statistics.
+    pub fn record(game: &Game, won: bool, hints: u32) -> Stats {
+        let mut stats = Stats::load();
+        stats.best_score = stats.best_score.max(game.score);
+        stats.best_tile = stats.best_tile.max(game.highest);
+        stats.games_played += 1;
+        if won {
+            stats.games_won += 1;
+        }
+        stats.total_score += game.score;
+        stats.total_moves += game.moves.len() as u64;
+        stats.hints_used += hints as u64;
+        *stats.max_tiles.entry(game.highest).or_insert(0) += 1;
+        storage::save(STATS, &stats);
+        stats
+    }
+
+    pub fn average_score(&self) -> u64 {
+        if self.games_played == 0 {
+            0
+        } else {
+            self.total_score / self.games_played as u64
+        }
+    }
+}
@@ -0,0 +1,41 @@
+//! JSON values kept in `window.localStorage`, so they outlive a reload.
+use log::warn;
+use mogwai::prelude::*;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use web_sys::Storage;
+
+fn local_storage() -> Option<Storage> {
+    window().local_storage().ok().flatten()
+}
+
+/// Reads back the value stored under `key`; unreadable entries count as missing.
+pub fn load<T: DeserializeOwned>(key: &str) -> Option<T> {
+    let json = local_storage()?.get_item(key).ok()??;
+    match serde_json::from_str(&json) {
+        Ok(value) => Some(value),
+        Err(err) => {
+            warn!("ignoring stored {}: {}", key, err);
+            None
+        }
+    }
+}
+
+pub fn save<T: Serialize>(key: &str, value: &T) {
+    let storage = match local_storage() {
+        Some(storage) => storage,
+        None => return,
+    };
+    let stored = serde_json::to_string(value)
+        .map_err(|err| err.to_string())
+        .and_then(|json| storage.set_item(key, &json).map_err(|err| format!("{:?}", err)));
+    if let Err(err) = stored {
+        warn!("couldn't store {}: {}", key, err);
+    }
+}
+
+pub fn remove(key: &str) {
+    if let Some(storage) = local_storage() {
+        storage.remove_item(key).ok();
+    }
+}